@@ -0,0 +1,24 @@
+; Teletype echo: keys are printed back as they are typed, Return starts a new
+; line and Escape halts.
+;       cargo run -- progs/echo.asm --teletype 3 --quiet
+loop:   INP 3
+        STA key
+        XOR esc
+        ADD minus1      ; only zero goes negative
+        JMA done        ; the key was Escape
+        LDA key
+        OUT 3
+        XOR cr
+        ADD minus1
+        JMA return
+        JMP loop
+return: LDA lf
+        OUT 3
+        JMP loop
+done:   HLT
+
+key:    DW 0
+esc:    DW 0x1B00
+cr:     DW 0x0D00
+lf:     DW 0x0A00
+minus1: DW 0xFFFF
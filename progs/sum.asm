@@ -0,0 +1,10 @@
+; Add two numbers and store the result
+        ORG 0x000
+start:  LDA x           ; A <- x
+        ADD y           ; A <- A + y
+        STA sum
+        HLT
+
+x:      DW 5
+y:      DW 'A' - '0'    ; 17
+sum:    DATA 0
@@ -0,0 +1,560 @@
+//! # Blue Assembler
+//!
+//! A two-pass assembler turning mnemonic source into memory images.
+//!
+//! ```text
+//! ; add two numbers
+//!         ORG 0x000
+//! start:  LDA x
+//!         ADD y
+//!         STA sum
+//!         HLT
+//! x:      DW 5
+//! y:      DW 'A' - '0'
+//! sum:    DATA 0
+//! ```
+//!
+//! - Labels end with `:` and may stand alone on a line
+//! - `ORG expr` moves the location counter, `DATA`/`DW expr, ...` emit words
+//! - Literals are decimal (`42`), hex (`0x2A`), binary (`0b101010`) or characters
+//!   (`'*'`, `'\n'`); `*` is the address of the current statement
+//! - Expressions add and subtract literals and labels
+//! - `;` starts a comment
+//!
+//! The extended instructions SUB and CMP are encoded under the NOP opcode and take a
+//! page-zero operand (0x00-0xFF); they only execute on a machine with extended
+//! decoding enabled.
+
+use crate::{BlueRegister, Instruction, OperandKind, RAM_LENGTH};
+use std::{collections::BTreeMap, error::Error, fmt};
+
+/// An assembly error at a position in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// Line number (1-based)
+    pub line: usize,
+    /// Column number (1-based)
+    pub column: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl AsmError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+/// Output of a successful assembly
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assembly {
+    /// Memory image starting at address 0, up to the highest word emitted
+    pub words: Vec<u16>,
+    /// Label addresses
+    pub symbols: BTreeMap<String, BlueRegister>,
+}
+
+impl Assembly {
+    /// Image as little-endian bytes, the format loaded by `blue <file>`
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    /// Image as whitespace-separated hex words, the format of `progs/*.bin`
+    pub fn to_hex(&self) -> String {
+        let words: Vec<String> = self
+            .words
+            .iter()
+            .map(|word| format!("{word:04X}"))
+            .collect();
+        words.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Number(i64),
+    Colon,
+    Comma,
+    Plus,
+    Minus,
+    Star,
+}
+
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    column: usize,
+}
+
+/// Split one source line into tokens, stopping at a comment
+fn tokenize(text: &str, line: usize) -> Result<Vec<Spanned>, AsmError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let token = match c {
+            ';' => break,
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '\'' => {
+                let (value, len) = char_literal(&chars[i..])
+                    .ok_or_else(|| AsmError::new(line, column, "invalid character literal"))?;
+                i += len;
+                tokens.push(Spanned {
+                    token: Token::Number(value),
+                    column,
+                });
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| !c.is_ascii_alphanumeric() && *c != '_')
+                    .map_or(chars.len(), |len| i + len);
+                let literal: String = chars[i..end].iter().collect();
+                let value = parse_number(&literal).ok_or_else(|| {
+                    AsmError::new(line, column, format!("invalid number '{literal}'"))
+                })?;
+                i = end;
+                tokens.push(Spanned {
+                    token: Token::Number(value),
+                    column,
+                });
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' || c == '.' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| !c.is_alphanumeric() && *c != '_' && *c != '.')
+                    .map_or(chars.len(), |len| i + len);
+                let name: String = chars[i..end].iter().collect();
+                i = end;
+                tokens.push(Spanned {
+                    token: Token::Ident(name),
+                    column,
+                });
+                continue;
+            }
+            c => return Err(AsmError::new(line, column, format!("unexpected '{c}'"))),
+        };
+        tokens.push(Spanned { token, column });
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Parse `'c'` or `'\n'` style literals, returning the value and length in chars
+fn char_literal(chars: &[char]) -> Option<(i64, usize)> {
+    let (value, len) = match chars.get(1)? {
+        '\\' => {
+            let escaped = match chars.get(2)? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                c @ ('\\' | '\'') => *c,
+                _ => return None,
+            };
+            (escaped, 4)
+        }
+        '\'' => return None,
+        c => (*c, 3),
+    };
+    (chars.get(len - 1) == Some(&'\'')).then_some((i64::from(u32::from(value)), len))
+}
+
+/// Parse a decimal, `0x` hex or `0b` binary literal
+pub(crate) fn parse_number(literal: &str) -> Option<i64> {
+    let literal = literal.replace('_', "");
+    let lower = literal.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Number(i64),
+    Symbol(String),
+    Here,
+}
+
+/// Sum of signed terms, each with the column it starts at
+#[derive(Debug, Clone)]
+struct Expr {
+    terms: Vec<(bool, Term, usize)>,
+    column: usize,
+}
+
+impl Expr {
+    /// Evaluate against `symbols`, with `*` standing for `here`
+    fn eval(
+        &self,
+        symbols: &BTreeMap<String, BlueRegister>,
+        here: usize,
+        line: usize,
+    ) -> Result<i64, AsmError> {
+        let mut total: i64 = 0;
+        for (negative, term, column) in &self.terms {
+            let value =
+                match term {
+                    Term::Number(n) => *n,
+                    Term::Here => i64::try_from(here).unwrap_or(i64::MAX),
+                    Term::Symbol(name) => symbols
+                        .get(name)
+                        .map(|&addr| i64::from(addr))
+                        .ok_or_else(|| {
+                            AsmError::new(line, *column, format!("undefined label '{name}'"))
+                        })?,
+                };
+            total = if *negative {
+                total.saturating_sub(value)
+            } else {
+                total.saturating_add(value)
+            };
+        }
+        Ok(total)
+    }
+}
+
+/// Cursor over the tokens of one line
+struct Parser<'a> {
+    tokens: &'a [Spanned],
+    pos: usize,
+    line: usize,
+    /// Column just past the end of the line, for errors at end of input
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |t| t.column)
+    }
+
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        AsmError::new(self.line, self.column(), message)
+    }
+
+    fn expr(&mut self) -> Result<Expr, AsmError> {
+        let column = self.column();
+        let mut terms = Vec::new();
+        loop {
+            let mut negative = false;
+            while let Some(sign @ (Token::Plus | Token::Minus)) = self.peek() {
+                negative ^= *sign == Token::Minus;
+                self.pos += 1;
+            }
+            let term_column = self.column();
+            let term = match self.peek() {
+                Some(Token::Number(n)) => Term::Number(*n),
+                Some(Token::Ident(name)) => Term::Symbol(name.clone()),
+                Some(Token::Star) => Term::Here,
+                _ => return Err(self.error("expected a number, label or '*'")),
+            };
+            self.pos += 1;
+            terms.push((negative, term, term_column));
+
+            if !matches!(self.peek(), Some(Token::Plus | Token::Minus)) {
+                return Ok(Expr { terms, column });
+            }
+        }
+    }
+
+    fn expr_list(&mut self) -> Result<Vec<Expr>, AsmError> {
+        let mut exprs = vec![self.expr()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            exprs.push(self.expr()?);
+        }
+        Ok(exprs)
+    }
+
+    fn finish(&self) -> Result<(), AsmError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("unexpected text after statement")),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Body {
+    Instruction(Instruction, Option<Expr>),
+    Data(Vec<Expr>),
+}
+
+#[derive(Debug)]
+struct Statement {
+    line: usize,
+    column: usize,
+    address: usize,
+    body: Body,
+}
+
+/// Assemble `source` into a memory image
+///
+/// # Errors
+/// Returns every error found, in source order.
+pub fn assemble(source: &str) -> Result<Assembly, Vec<AsmError>> {
+    let mut symbols = BTreeMap::new();
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    let mut location = 0;
+
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        if let Err(e) = parse_line(text, line, &mut location, &mut symbols, &mut statements) {
+            errors.push(e);
+        }
+    }
+
+    let mut image = BTreeMap::new();
+    for statement in &statements {
+        if let Err(e) = emit(statement, &symbols, &mut image) {
+            errors.push(e);
+        }
+    }
+    if !errors.is_empty() {
+        errors.sort_by_key(|e| (e.line, e.column));
+        return Err(errors);
+    }
+
+    let mut words = vec![0; image.keys().next_back().map_or(0, |&addr| addr + 1)];
+    for (addr, word) in image {
+        words[addr] = word;
+    }
+    Ok(Assembly { words, symbols })
+}
+
+/// First pass over a line: record labels, advance the location counter
+fn parse_line(
+    text: &str,
+    line: usize,
+    location: &mut usize,
+    symbols: &mut BTreeMap<String, BlueRegister>,
+    statements: &mut Vec<Statement>,
+) -> Result<(), AsmError> {
+    let tokens = tokenize(text, line)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        line,
+        end: text.chars().count() + 1,
+    };
+
+    if let [
+        Spanned {
+            token: Token::Ident(label),
+            column,
+        },
+        Spanned {
+            token: Token::Colon,
+            ..
+        },
+        ..,
+    ] = tokens.as_slice()
+    {
+        if symbols.contains_key(label) {
+            return Err(AsmError::new(
+                line,
+                *column,
+                format!("duplicate label '{label}'"),
+            ));
+        }
+        let address = BlueRegister::try_from(*location)
+            .ok()
+            .filter(|&addr| usize::from(addr) < RAM_LENGTH)
+            .ok_or_else(|| AsmError::new(line, *column, "label is past the end of memory"))?;
+        symbols.insert(label.clone(), address);
+        parser.pos = 2;
+    }
+
+    let column = parser.column();
+    let Some(Token::Ident(op)) = parser.peek() else {
+        return match parser.peek() {
+            None => Ok(()),
+            Some(_) => Err(parser.error("expected a mnemonic or directive")),
+        };
+    };
+    let op = op.to_ascii_uppercase();
+    parser.pos += 1;
+
+    let body = match op.as_str() {
+        "ORG" => {
+            let expr = parser.expr()?;
+            parser.finish()?;
+            let value = expr.eval(symbols, *location, line)?;
+            *location = usize::try_from(value)
+                .ok()
+                .filter(|&addr| addr < RAM_LENGTH)
+                .ok_or_else(|| {
+                    AsmError::new(
+                        line,
+                        expr.column,
+                        format!("ORG address {value} out of range"),
+                    )
+                })?;
+            return Ok(());
+        }
+        "DATA" | "DW" => Body::Data(parser.expr_list()?),
+        _ => {
+            let instruction = Instruction::from_mnemonic(&op)
+                .ok_or_else(|| AsmError::new(line, column, format!("unknown mnemonic '{op}'")))?;
+            let operand = if parser.peek().is_some() {
+                Some(parser.expr()?)
+            } else {
+                None
+            };
+            Body::Instruction(instruction, operand)
+        }
+    };
+    parser.finish()?;
+
+    let size = match &body {
+        Body::Data(exprs) => exprs.len(),
+        Body::Instruction(..) => 1,
+    };
+    statements.push(Statement {
+        line,
+        column,
+        address: *location,
+        body,
+    });
+    *location += size;
+    Ok(())
+}
+
+/// Second pass over a statement: evaluate operands and place words in the image
+fn emit(
+    statement: &Statement,
+    symbols: &BTreeMap<String, BlueRegister>,
+    image: &mut BTreeMap<usize, u16>,
+) -> Result<(), AsmError> {
+    let line = statement.line;
+    let words = match &statement.body {
+        Body::Instruction(instruction, operand) => {
+            vec![encode(*instruction, operand.as_ref(), statement, symbols)?]
+        }
+        Body::Data(exprs) => exprs
+            .iter()
+            .enumerate()
+            .map(|(i, expr)| {
+                let value = expr.eval(symbols, statement.address + i, line)?;
+                if (-0x8000..=0xFFFF).contains(&value) {
+                    Ok((value & 0xFFFF) as u16)
+                } else {
+                    Err(AsmError::new(
+                        line,
+                        expr.column,
+                        format!("value {value} does not fit in a word"),
+                    ))
+                }
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    for (i, word) in words.into_iter().enumerate() {
+        let addr = statement.address + i;
+        if addr >= RAM_LENGTH {
+            return Err(AsmError::new(
+                line,
+                statement.column,
+                "program exceeds memory",
+            ));
+        }
+        if image.insert(addr, word).is_some() {
+            return Err(AsmError::new(
+                line,
+                statement.column,
+                format!("address {addr:#05x} is already assembled"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Encode an instruction word
+fn encode(
+    instruction: Instruction,
+    operand: Option<&Expr>,
+    statement: &Statement,
+    symbols: &BTreeMap<String, BlueRegister>,
+) -> Result<u16, AsmError> {
+    let line = statement.line;
+    let mnemonic = instruction.mnemonic();
+    let kind = instruction.operand_kind();
+    let value = match operand {
+        Some(expr) if kind == OperandKind::None => {
+            return Err(AsmError::new(
+                line,
+                expr.column,
+                format!("{mnemonic} takes no operand"),
+            ));
+        }
+        Some(expr) => {
+            let value = expr.eval(symbols, statement.address, line)?;
+            let max = instruction.operand_max();
+            if !(0..=i64::from(max)).contains(&value) {
+                let message = match instruction {
+                    // A flag jump reaches further through a JMP it lands on in page 0
+                    Instruction::Jmz | Instruction::Jmc | Instruction::Jmv if value > 0 => {
+                        format!(
+                            "{mnemonic} only reaches page 0 (0-{max:#x}), not {value:#x}; \
+                             {mnemonic} to a 'JMP {value:#x}' placed in page 0 instead"
+                        )
+                    }
+                    _ if instruction.is_extended() && value > 0 => {
+                        format!("{mnemonic} only addresses page 0 (0-{max:#x}), not {value:#x}")
+                    }
+                    _ => format!("operand {value} out of range for {mnemonic} (0-{max:#x})"),
+                };
+                return Err(AsmError::new(line, expr.column, message));
+            }
+            value as u16
+        }
+        None if kind == OperandKind::None => 0,
+        None => {
+            return Err(AsmError::new(
+                line,
+                statement.column,
+                format!("{mnemonic} requires an operand"),
+            ));
+        }
+    };
+    Ok(instruction.encode(value))
+}
@@ -9,12 +9,31 @@
 //! - 15-bit signed integers (two's complement) + sign bit
 //! - 4-bit opcode with 12-bit address field
 //! - 8-step clock-driven execution cycle
+//!
+//! ## Embedding
+//!
+//! [`BlueComputer::run_program`] drives the interactive console. Hosts that need
+//! control over execution use [`BlueComputer::step_tick`],
+//! [`BlueComputer::step_instruction`] and [`BlueComputer::run_until`], which never
+//! touch stdin/stdout and report why they stopped through [`StopReason`].
 
-use std::io;
+use crate::{
+    breakpoint::{Breakpoints, WatchHit},
+    debugger::{self, Debugger, Flow},
+    device::{DeviceBus, EndOfInputPolicy, UnattachedPolicy},
+    explain::MicroEvent,
+    journal::{Granularity, Journal},
+    microcode::{self, Condition, MicroOp, MicroStep, Microcode, Routine},
+    snapshot::Snapshot,
+};
+use std::{error::Error, fmt, io, mem, str::FromStr, sync::Arc};
 
 /// Total memory capacity in words
 pub const RAM_LENGTH: usize = 4096;
 
+/// Address the processor jumps to when it takes an interrupt
+pub const INTERRUPT_VECTOR: BlueRegister = 0x001;
+
 /// Type representing all registers in the Blue computer
 pub type BlueRegister = u16;
 
@@ -25,14 +44,174 @@ const FLAG_OVERFLOW: BlueRegister = 0b0100;
 const FLAG_NEGATIVE: BlueRegister = 0b1000;
 
 /// Current execution state of the processor
-#[derive(Debug, PartialEq, Eq)]
-enum State {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
     /// Instruction execution phase
     Execute,
     /// Instruction fetch phase
     Fetch,
 }
 
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Execute => write!(f, "execute"),
+            Self::Fetch => write!(f, "fetch"),
+        }
+    }
+}
+
+impl FromStr for State {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "execute" => Ok(Self::Execute),
+            "fetch" => Ok(Self::Fetch),
+            _ => Err(format!("state '{s}' is not fetch or execute")),
+        }
+    }
+}
+
+/// One clock pulse of an instruction's microcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MicroTick {
+    /// Opcode number of the instruction, as [`Instruction::opcode_of`] gives it
+    pub opcode: u8,
+    /// Fetch or Execute cycle
+    pub state: State,
+    /// Clock pulse (0-7)
+    pub tick: u8,
+}
+
+impl fmt::Display for MicroTick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} tick {}",
+            microcode::opcode_name(usize::from(self.opcode)),
+            self.state,
+            self.tick
+        )
+    }
+}
+
+/// Which variant of the Blue instruction set the machine implements
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IsaProfile {
+    /// Foster's 1970 machine as in the book: 16 instructions, no status flags, and
+    /// signed overflow in ADD halts the processor
+    #[default]
+    Foster,
+    /// Foster's instruction set with the status flags register (Z, C, V, N) updated by
+    /// ADD and the logic instructions; overflow still halts
+    Flags,
+    /// Status flags plus the extended instructions under NOP; overflow sets V and
+    /// execution continues
+    Extended,
+}
+
+impl IsaProfile {
+    /// Every profile, from most to least faithful
+    pub const ALL: [Self; 3] = [Self::Foster, Self::Flags, Self::Extended];
+
+    /// Name used on the command line
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Foster => "foster",
+            Self::Flags => "flags",
+            Self::Extended => "extended",
+        }
+    }
+
+    /// Whether arithmetic and logic instructions update the flags register
+    pub const fn has_flags(self) -> bool {
+        !matches!(self, Self::Foster)
+    }
+
+    /// Whether the sub-opcodes under NOP decode as extended instructions
+    pub const fn has_extended_opcodes(self) -> bool {
+        matches!(self, Self::Extended)
+    }
+
+    /// What signed overflow in ADD and SUB does unless the machine overrides it
+    pub const fn overflow_policy(self) -> OverflowPolicy {
+        match self {
+            Self::Foster | Self::Flags => OverflowPolicy::Halt,
+            Self::Extended => OverflowPolicy::Flag,
+        }
+    }
+}
+
+impl fmt::Display for IsaProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for IsaProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown ISA profile '{s}' (foster, flags, extended)"))
+    }
+}
+
+/// What ADD and SUB do when the signed result overflows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Halt the processor (`RunState::Halted(Overflow)`), reporting [`StopReason::Overflow`]
+    Halt,
+    /// Save PC in IPC, disable interrupts and continue at the handler address, like an
+    /// interrupt; RTI returns
+    Trap(BlueRegister),
+    /// Set the V flag and continue
+    Flag,
+    /// Continue with the wrapped result, leaving V clear
+    Wrap,
+    /// Stop with a [`Fault`] of kind [`FaultKind::Overflow`]
+    Fault,
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Halt => f.write_str("halt"),
+            Self::Trap(addr) => write!(f, "trap:{addr:03x}"),
+            Self::Flag => f.write_str("flag"),
+            Self::Wrap => f.write_str("wrap"),
+            Self::Fault => f.write_str("fault"),
+        }
+    }
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+
+    /// Parse `halt`, `flag`, `wrap`, `fault` or `trap:<hex address>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.to_ascii_lowercase();
+        match text.as_str() {
+            "halt" => Ok(Self::Halt),
+            "flag" => Ok(Self::Flag),
+            "wrap" => Ok(Self::Wrap),
+            "fault" => Ok(Self::Fault),
+            _ => text
+                .strip_prefix("trap:")
+                .map(|addr| addr.strip_prefix("0x").unwrap_or(addr))
+                .and_then(|addr| BlueRegister::from_str_radix(addr, 16).ok())
+                .filter(|&addr| usize::from(addr) < RAM_LENGTH)
+                .map(Self::Trap)
+                .ok_or_else(|| {
+                    format!("unknown overflow policy '{s}' (halt, flag, wrap, fault, trap:<addr>)")
+                }),
+        }
+    }
+}
+
 /// Debug configuration settings
 #[derive(Debug, Default)]
 pub struct DebugSettings {
@@ -53,6 +232,292 @@ pub struct IoState {
     pub ready: bool,
 }
 
+/// Kind of error that stopped the processor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaultKind {
+    /// The instruction register does not hold a valid opcode
+    InvalidOpcode(BlueRegister),
+    /// A memory access fell outside RAM
+    AddressOutOfRange(BlueRegister),
+    /// A device or console transfer failed
+    Io(String),
+    /// INP/OUT selected a device number with nothing attached
+    UnattachedDevice(u8),
+    /// INP read from a device with no input left
+    EndOfInput(u8),
+    /// ADD or SUB overflowed under [`OverflowPolicy::Fault`]
+    Overflow,
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOpcode(word) => write!(f, "invalid opcode in {word:04x}"),
+            Self::AddressOutOfRange(addr) => write!(f, "address {addr:04x} out of range"),
+            Self::Io(msg) => write!(f, "I/O error: {msg}"),
+            Self::UnattachedDevice(device) => write!(f, "no device attached at {device:02x}"),
+            Self::EndOfInput(device) => write!(f, "end of input on device {device:02x}"),
+            Self::Overflow => f.write_str("arithmetic overflow"),
+        }
+    }
+}
+
+/// An emulator fault, with the registers identifying the offending instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    /// What went wrong
+    pub kind: FaultKind,
+    /// Program Counter when the fault was raised
+    pub pc: BlueRegister,
+    /// Instruction Register when the fault was raised
+    pub ir: BlueRegister,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (PC: {:04x} IR: {:04x})", self.kind, self.pc, self.ir)
+    }
+}
+
+impl Error for Fault {}
+
+/// Processor state apart from RAM, as stored in snapshots and the history journal
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Registers {
+    pub(crate) execute: bool,
+    pub(crate) clock_pulse: u8,
+    pub(crate) power: bool,
+    pub(crate) transfer_active: bool,
+    pub(crate) ready: bool,
+    pub(crate) pc: BlueRegister,
+    pub(crate) a: BlueRegister,
+    pub(crate) z: BlueRegister,
+    pub(crate) sr: BlueRegister,
+    pub(crate) mar: BlueRegister,
+    pub(crate) mbr: BlueRegister,
+    pub(crate) ir: BlueRegister,
+    pub(crate) dsl: BlueRegister,
+    pub(crate) dil: BlueRegister,
+    pub(crate) dol: BlueRegister,
+    pub(crate) flags: BlueRegister,
+    pub(crate) interrupts: bool,
+    pub(crate) ipc: BlueRegister,
+}
+
+/// How the emulator carries out instructions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Sequence every micro-operation tick by tick
+    #[default]
+    CycleAccurate,
+    /// Execute whole instructions at once where the result is identical
+    ///
+    /// Only the built-in microcode is interpreted this way; INP, OUT, faulting
+    /// instructions and custom microcode still run tick by tick.
+    Fast,
+}
+
+/// Reason the emulator handed control back to its caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// The processor halted (HLT) or is powered off
+    Halted,
+    /// ADD or SUB overflowed under [`OverflowPolicy::Halt`]
+    Overflow,
+    /// The next instruction to fetch is at a breakpoint address
+    Breakpoint(BlueRegister),
+    /// The instruction that just finished accessed a watched address
+    Watchpoint(WatchHit),
+    /// The next clock pulse has a tick breakpoint
+    TickBreakpoint(MicroTick),
+    /// An INP/OUT transfer is waiting for the host, see [`BlueComputer::pending_io`]
+    WaitingForIo,
+    /// The requested number of cycles elapsed
+    CycleLimit,
+    /// The processor could not continue
+    Fault(Fault),
+}
+
+/// Whether the processor clock is running and, if not, why it stopped
+///
+/// The states follow the console of the original machine: HLT stops the clock
+/// with the power still on, and pressing START (`c` on the console) carries on
+/// from the word after the HLT.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum RunState {
+    /// The power switch is off
+    #[default]
+    PoweredOff,
+    /// The clock is running
+    Running,
+    /// Stopped by the operator or the debugger; the next step resumes
+    Paused(PauseReason),
+    /// Stopped by the program; stays stopped until the machine is started again
+    Halted(HaltReason),
+    /// The processor could not continue; stays stopped until powered on again
+    Faulted(Fault),
+}
+
+impl fmt::Display for RunState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PoweredOff => write!(f, "powered off"),
+            Self::Running => write!(f, "running"),
+            Self::Paused(reason) => write!(f, "paused ({reason})"),
+            Self::Halted(reason) => write!(f, "halted ({reason})"),
+            Self::Faulted(fault) => write!(f, "faulted ({fault})"),
+        }
+    }
+}
+
+/// Why the clock was paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// The next instruction to fetch is at a breakpoint address
+    Breakpoint(BlueRegister),
+    /// The instruction that just finished accessed a watched address
+    Watchpoint(WatchHit),
+    /// The next clock pulse has a tick breakpoint
+    TickBreakpoint(MicroTick),
+    /// A single step or tick finished
+    Step,
+    /// The operator stopped the machine, or a saved state was loaded
+    Console,
+}
+
+impl fmt::Display for PauseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Breakpoint(addr) => write!(f, "breakpoint at {addr:04x}"),
+            Self::Watchpoint(hit) => write!(f, "{hit}"),
+            Self::TickBreakpoint(tick) => write!(f, "tick breakpoint before {tick}"),
+            Self::Step => write!(f, "single step"),
+            Self::Console => write!(f, "console"),
+        }
+    }
+}
+
+/// What halted the processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HaltReason {
+    /// A HLT instruction
+    Instruction,
+    /// ADD or SUB overflowed under [`OverflowPolicy::Halt`]
+    Overflow,
+    /// INP ran out of input under [`EndOfInputPolicy::Halt`]
+    EndOfInput,
+}
+
+impl fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instruction => write!(f, "HLT"),
+            Self::Overflow => write!(f, "arithmetic overflow"),
+            Self::EndOfInput => write!(f, "end of input"),
+        }
+    }
+}
+
+/// An I/O transfer waiting to be serviced by the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoRequest {
+    /// INP is waiting for a byte from the selected device
+    Input {
+        /// Device selector latched in DSL
+        device: u8,
+    },
+    /// OUT is waiting for the selected device to accept a byte
+    Output {
+        /// Device selector latched in DSL
+        device: u8,
+        /// Byte latched in DOL
+        byte: u8,
+    },
+}
+
+/// A register the console can examine and deposit into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterName {
+    /// Program Counter
+    Pc,
+    /// Accumulator
+    A,
+    /// Temporary calculation register
+    Z,
+    /// Console Switch Register
+    Sr,
+    /// Memory Address Register
+    Mar,
+    /// Memory Buffer Register
+    Mbr,
+    /// Instruction Register
+    Ir,
+    /// Device Selector
+    Dsl,
+    /// Data Input Register
+    Dil,
+    /// Data Output Register
+    Dol,
+    /// Processor status flags
+    Flags,
+    /// Interrupt return address
+    Ipc,
+}
+
+impl RegisterName {
+    /// Every register, in the order they are displayed
+    pub const ALL: [Self; 12] = [
+        Self::Pc,
+        Self::A,
+        Self::Z,
+        Self::Sr,
+        Self::Mar,
+        Self::Mbr,
+        Self::Ir,
+        Self::Dsl,
+        Self::Dil,
+        Self::Dol,
+        Self::Flags,
+        Self::Ipc,
+    ];
+
+    /// Upper-case name, as printed in register dumps
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Pc => "PC",
+            Self::A => "A",
+            Self::Z => "Z",
+            Self::Sr => "SR",
+            Self::Mar => "MAR",
+            Self::Mbr => "MBR",
+            Self::Ir => "IR",
+            Self::Dsl => "DSL",
+            Self::Dil => "DIL",
+            Self::Dol => "DOL",
+            Self::Flags => "FLAGS",
+            Self::Ipc => "IPC",
+        }
+    }
+}
+
+impl fmt::Display for RegisterName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for RegisterName {
+    type Err = String;
+
+    /// Parse a register name in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|register| register.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown register '{s}'"))
+    }
+}
+
 /// The complete Blue computer emulator
 #[derive(Debug)]
 pub struct BlueComputer {
@@ -62,8 +527,10 @@ pub struct BlueComputer {
     debug: DebugSettings,
     /// I/O operation state
     io: IoState,
-    /// Power state (on/off)
-    power: bool,
+    /// Devices attached by DSL selector
+    bus: DeviceBus,
+    /// Whether the clock is running, and why not
+    run_state: RunState,
 
     // Registers
     /// Program Counter (12-bit effective)
@@ -90,16 +557,36 @@ pub struct BlueComputer {
     dol: BlueRegister,
     /// Processor status flags
     flags: BlueRegister,
+    /// Whether device interrupts are taken
+    interrupts: bool,
+    /// Interrupt return address
+    ipc: BlueRegister,
+    /// Overflow handling, if not the profile's
+    overflow: Option<OverflowPolicy>,
     /// Current clock pulse (0-7)
     clock_pulse: u8,
-    /// Debug breakpoints
-    breakpoints: Vec<BlueRegister>,
+    /// Debug breakpoints and watchpoints
+    breakpoints: Breakpoints,
+    /// Address of the instruction being executed
+    instruction: BlueRegister,
+    /// Watchpoint triggered by the instruction being executed
+    watch_hit: Option<WatchHit>,
+    /// Instruction set variant
+    profile: IsaProfile,
+    /// Micro-operations sequenced for each opcode
+    microcode: Arc<Microcode>,
+    /// Tick-by-tick or instruction-level execution
+    mode: ExecutionMode,
+    /// History kept for reverse execution
+    journal: Journal,
+    /// Micro-operations not yet taken by the host, while explain mode is on
+    explain: Option<Vec<MicroEvent>>,
 }
 
 /// All supported instructions with their numeric opcodes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
-enum Instruction {
+pub enum Instruction {
     Hlt = 0, // Halt the processor
     Add,     // Add memory to accumulator
     Xor,     // Bitwise XOR
@@ -118,8 +605,18 @@ enum Instruction {
     Nop,     // No operation
     Sub,     // Subtract (extension)
     Cmp,     // Compare (extension)
+    Ein,     // Enable interrupts (extension)
+    Din,     // Disable interrupts (extension)
+    Rti,     // Return from interrupt (extension)
+    Jmz,     // Jump if zero flag set (extension)
+    Jmc,     // Jump if carry flag set (extension)
+    Jmv,     // Jump if overflow flag set (extension)
 }
 
+/// Extended instructions live under the NOP opcode: bits 8-11 hold a sub-opcode
+/// (0 is a plain NOP) and bits 0-7 an operand in page zero (0x000-0x0FF).
+const EXTENDED_OPCODE: u16 = 0xF;
+
 impl TryFrom<u16> for Instruction {
     type Error = &'static str;
 
@@ -144,14 +641,176 @@ impl TryFrom<u16> for Instruction {
             15 => Ok(Self::Nop),
             16 => Ok(Self::Sub),
             17 => Ok(Self::Cmp),
+            18 => Ok(Self::Ein),
+            19 => Ok(Self::Din),
+            20 => Ok(Self::Rti),
+            21 => Ok(Self::Jmz),
+            22 => Ok(Self::Jmc),
+            23 => Ok(Self::Jmv),
             _ => Err("Invalid instruction opcode"),
         }
     }
 }
 
+/// Meaning of the 12-bit field of an instruction word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    /// Unused by the instruction
+    None,
+    /// Memory address
+    Address,
+    /// Device selector (low 6 bits)
+    Device,
+}
+
+impl Instruction {
+    /// Every instruction, in opcode order
+    pub const ALL: [Self; 24] = [
+        Self::Hlt,
+        Self::Add,
+        Self::Xor,
+        Self::And,
+        Self::Ior,
+        Self::Not,
+        Self::Lda,
+        Self::Sta,
+        Self::Srj,
+        Self::Jma,
+        Self::Jmp,
+        Self::Inp,
+        Self::Out,
+        Self::Ral,
+        Self::Csa,
+        Self::Nop,
+        Self::Sub,
+        Self::Cmp,
+        Self::Ein,
+        Self::Din,
+        Self::Rti,
+        Self::Jmz,
+        Self::Jmc,
+        Self::Jmv,
+    ];
+
+    /// Numeric opcode
+    pub const fn opcode(self) -> u8 {
+        self as u8
+    }
+
+    /// Decode a word as `profile` would, recognising extended sub-opcodes if it has them
+    ///
+    /// # Errors
+    /// Fails for an extended sub-opcode with no instruction assigned.
+    pub fn decode(word: u16, profile: IsaProfile) -> Result<Self, &'static str> {
+        Self::ALL
+            .get(usize::from(Self::opcode_of(word, profile)))
+            .copied()
+            .ok_or("Invalid extended opcode")
+    }
+
+    /// Opcode number of a word as `profile` decodes it
+    ///
+    /// Extended sub-opcodes 1-15 under NOP map to opcode numbers 16-30.
+    pub const fn opcode_of(word: u16, profile: IsaProfile) -> u8 {
+        let primary = (word >> 12) as u8;
+        let sub = ((word >> 8) & 0x000F) as u8;
+        if primary == EXTENDED_OPCODE as u8 && sub != 0 && profile.has_extended_opcodes() {
+            primary + sub
+        } else {
+            primary
+        }
+    }
+
+    /// Whether the instruction is encoded in the extended sub-opcode space
+    pub const fn is_extended(self) -> bool {
+        self.opcode() > EXTENDED_OPCODE as u8
+    }
+
+    /// Largest operand the instruction word can hold
+    pub const fn operand_max(self) -> u16 {
+        match self.operand_kind() {
+            OperandKind::Device => 0x3F,
+            _ if self.is_extended() => 0xFF,
+            _ => 0xFFF,
+        }
+    }
+
+    /// Extract the operand field from an instruction word
+    pub const fn operand(self, word: u16) -> u16 {
+        if self.is_extended() {
+            word & 0x00FF
+        } else {
+            word & 0x0FFF
+        }
+    }
+
+    /// Build an instruction word, masking `operand` to the field width
+    pub const fn encode(self, operand: u16) -> u16 {
+        if self.is_extended() {
+            let code = self.opcode() as u16 - EXTENDED_OPCODE;
+            (EXTENDED_OPCODE << 12) | (code << 8) | (operand & 0x00FF)
+        } else {
+            ((self.opcode() as u16) << 12) | (operand & 0x0FFF)
+        }
+    }
+
+    /// Assembler mnemonic
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Hlt => "HLT",
+            Self::Add => "ADD",
+            Self::Xor => "XOR",
+            Self::And => "AND",
+            Self::Ior => "IOR",
+            Self::Not => "NOT",
+            Self::Lda => "LDA",
+            Self::Sta => "STA",
+            Self::Srj => "SRJ",
+            Self::Jma => "JMA",
+            Self::Jmp => "JMP",
+            Self::Inp => "INP",
+            Self::Out => "OUT",
+            Self::Ral => "RAL",
+            Self::Csa => "CSA",
+            Self::Nop => "NOP",
+            Self::Sub => "SUB",
+            Self::Cmp => "CMP",
+            Self::Ein => "EIN",
+            Self::Din => "DIN",
+            Self::Rti => "RTI",
+            Self::Jmz => "JMZ",
+            Self::Jmc => "JMC",
+            Self::Jmv => "JMV",
+        }
+    }
+
+    /// Look up an instruction by mnemonic, ignoring case
+    pub fn from_mnemonic(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|instruction| instruction.mnemonic().eq_ignore_ascii_case(name))
+    }
+
+    /// What the 12-bit field of the instruction holds
+    pub const fn operand_kind(self) -> OperandKind {
+        match self {
+            Self::Hlt
+            | Self::Not
+            | Self::Ral
+            | Self::Csa
+            | Self::Nop
+            | Self::Ein
+            | Self::Din
+            | Self::Rti => OperandKind::None,
+            Self::Inp | Self::Out => OperandKind::Device,
+            _ => OperandKind::Address,
+        }
+    }
+}
+
 impl BlueComputer {
     /// Create a new Blue computer instance with all registers zeroed
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             state: State::Fetch,
             debug: DebugSettings {
@@ -163,7 +822,8 @@ impl BlueComputer {
                 transfer_active: false,
                 ready: false,
             },
-            power: false,
+            bus: DeviceBus::new(),
+            run_state: RunState::PoweredOff,
             pc: 0x00,
             a: 0,
             z: 0,
@@ -176,30 +836,531 @@ impl BlueComputer {
             dil: 0,
             dol: 0,
             flags: 0,
+            interrupts: false,
+            ipc: 0,
+            overflow: None,
             clock_pulse: 0,
-            breakpoints: Vec::new(),
+            breakpoints: Breakpoints::default(),
+            instruction: 0,
+            watch_hit: None,
+            profile: IsaProfile::Foster,
+            microcode: Microcode::builtin(),
+            mode: ExecutionMode::CycleAccurate,
+            journal: Journal::new(0, Granularity::Instruction),
+            explain: None,
         }
     }
 
-    /// Power on the computer
-    fn press_on(&mut self) {
-        println!("Pressed ON");
-        self.power = true;
+    /// Power on the computer and start the clock, clearing any halt or fault
+    pub fn press_on(&mut self) {
+        self.run_state = RunState::Running;
     }
 
     /// Power off the computer
-    fn _press_off(&mut self) {
-        println!("Pressed OFF");
-        self.power = false;
+    pub fn press_off(&mut self) {
+        self.run_state = RunState::PoweredOff;
+    }
+
+    /// Whether the computer is powered on, running or not
+    pub const fn is_powered(&self) -> bool {
+        !matches!(self.run_state, RunState::PoweredOff)
+    }
+
+    /// Whether the clock is running and, if not, why it stopped
+    pub const fn run_state(&self) -> &RunState {
+        &self.run_state
+    }
+
+    /// Whether the clock is running
+    const fn is_running(&self) -> bool {
+        matches!(self.run_state, RunState::Running)
+    }
+
+    /// Restart a paused or halted machine, as the console's START switch does
+    ///
+    /// A halted machine carries on from the word after the HLT. Returns `false`,
+    /// leaving the state alone, when the machine is powered off or faulted.
+    pub fn resume(&mut self) -> bool {
+        match self.run_state {
+            RunState::Running | RunState::Paused(_) | RunState::Halted(_) => {
+                self.run_state = RunState::Running;
+                true
+            }
+            RunState::PoweredOff | RunState::Faulted(_) => false,
+        }
+    }
+
+    /// Stop the clock if it is running, reporting `reason`
+    pub fn pause(&mut self, reason: PauseReason) {
+        if self.is_running() {
+            self.run_state = RunState::Paused(reason);
+        }
+    }
+
+    /// Current value of a register
+    pub const fn register(&self, name: RegisterName) -> BlueRegister {
+        match name {
+            RegisterName::Pc => self.pc,
+            RegisterName::A => self.a,
+            RegisterName::Z => self.z,
+            RegisterName::Sr => self.sr,
+            RegisterName::Mar => self.mar,
+            RegisterName::Mbr => self.mbr,
+            RegisterName::Ir => self.ir,
+            RegisterName::Dsl => self.dsl,
+            RegisterName::Dil => self.dil,
+            RegisterName::Dol => self.dol,
+            RegisterName::Flags => self.flags,
+            RegisterName::Ipc => self.ipc,
+        }
+    }
+
+    /// Overwrite a register, as depositing from the console does
+    pub const fn set_register(&mut self, name: RegisterName, value: BlueRegister) {
+        let register = match name {
+            RegisterName::Pc => &mut self.pc,
+            RegisterName::A => &mut self.a,
+            RegisterName::Z => &mut self.z,
+            RegisterName::Sr => &mut self.sr,
+            RegisterName::Mar => &mut self.mar,
+            RegisterName::Mbr => &mut self.mbr,
+            RegisterName::Ir => &mut self.ir,
+            RegisterName::Dsl => &mut self.dsl,
+            RegisterName::Dil => &mut self.dil,
+            RegisterName::Dol => &mut self.dol,
+            RegisterName::Flags => &mut self.flags,
+            RegisterName::Ipc => &mut self.ipc,
+        };
+        *register = value;
+    }
+
+    /// Clear RAM and copy `program` to the start of memory
+    ///
+    /// Words beyond [`RAM_LENGTH`] are ignored.
+    pub fn load_program(&mut self, program: &[u16]) {
+        let len = program.len().min(RAM_LENGTH);
+        self.ram = [0; RAM_LENGTH];
+        self.ram[..len].copy_from_slice(&program[..len]);
+    }
+
+    /// Program Counter
+    pub const fn pc(&self) -> BlueRegister {
+        self.pc
+    }
+
+    /// Accumulator
+    pub const fn a(&self) -> BlueRegister {
+        self.a
+    }
+
+    /// Instruction Register
+    pub const fn ir(&self) -> BlueRegister {
+        self.ir
+    }
+
+    /// Processor status flags
+    pub const fn flags(&self) -> BlueRegister {
+        self.flags
+    }
+
+    /// Whether device interrupts are enabled (EIN/DIN)
+    pub const fn interrupts_enabled(&self) -> bool {
+        self.interrupts
+    }
+
+    /// Interrupt return address, saved when the last interrupt was taken
+    pub const fn ipc(&self) -> BlueRegister {
+        self.ipc
+    }
+
+    /// Current clock pulse (0-7)
+    pub const fn clock_pulse(&self) -> u8 {
+        self.clock_pulse
+    }
+
+    /// Whether the processor is in a Fetch or an Execute cycle
+    pub const fn state(&self) -> State {
+        self.state
+    }
+
+    /// The clock pulse the processor runs next, with the opcode of its instruction
+    ///
+    /// During a Fetch cycle the instruction is the word being fetched, which only
+    /// reaches the IR partway through. `None` if that word is outside memory.
+    pub fn next_tick(&self) -> Option<MicroTick> {
+        let word = match self.state {
+            State::Execute => self.ir,
+            State::Fetch if self.clock_pulse == 0 => self.read_memory(self.mar)?,
+            State::Fetch => self.read_memory(self.instruction)?,
+        };
+        Some(MicroTick {
+            opcode: Instruction::opcode_of(word, self.profile),
+            state: self.state,
+            tick: self.clock_pulse,
+        })
+    }
+
+    /// Main memory
+    pub const fn ram(&self) -> &[u16; RAM_LENGTH] {
+        &self.ram
+    }
+
+    /// Read a word of memory, `None` if `addr` is outside RAM
+    pub fn read_memory(&self, addr: BlueRegister) -> Option<u16> {
+        self.ram.get(usize::from(addr)).copied()
+    }
+
+    /// Write a word of memory, returning `false` if `addr` is outside RAM
+    pub fn write_memory(&mut self, addr: BlueRegister, value: u16) -> bool {
+        self.ram
+            .get_mut(usize::from(addr))
+            .map(|word| *word = value)
+            .is_some()
+    }
+
+    /// Set the console Switch Register read by CSA
+    pub const fn set_switches(&mut self, value: BlueRegister) {
+        self.sr = value;
+    }
+
+    /// Devices attached to the I/O bus
+    pub const fn bus(&self) -> &DeviceBus {
+        &self.bus
+    }
+
+    /// Attach, detach and configure I/O devices
+    pub const fn bus_mut(&mut self) -> &mut DeviceBus {
+        &mut self.bus
+    }
+
+    /// Stop execution when the next instruction to fetch is at `addr`, returning
+    /// the id of the new breakpoint
+    pub fn add_breakpoint(&mut self, addr: BlueRegister) -> u32 {
+        self.breakpoints.add(addr)
+    }
+
+    /// Breakpoints set on the machine
+    pub const fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+
+    /// Mutable access to the breakpoints, to add, change or delete them
+    pub const fn breakpoints_mut(&mut self) -> &mut Breakpoints {
+        &mut self.breakpoints
+    }
+
+    /// Count a hit on the breakpoints at the PC, returning whether to stop
+    fn breakpoint_hit(&mut self) -> bool {
+        let mut breakpoints = mem::take(&mut self.breakpoints);
+        let stop = breakpoints.hit(self.pc, self);
+        self.breakpoints = breakpoints;
+        stop
+    }
+
+    /// Instruction set variant
+    pub const fn profile(&self) -> IsaProfile {
+        self.profile
+    }
+
+    /// Select the instruction set variant
+    ///
+    /// Defaults to [`IsaProfile::Foster`], the machine exactly as in the book.
+    pub const fn set_profile(&mut self, profile: IsaProfile) {
+        self.profile = profile;
+    }
+
+    /// What signed overflow in ADD and SUB does
+    pub const fn overflow_policy(&self) -> OverflowPolicy {
+        match self.overflow {
+            Some(policy) => policy,
+            None => self.profile.overflow_policy(),
+        }
+    }
+
+    /// Override what signed overflow does; `None` goes back to the profile's policy
+    pub const fn set_overflow_policy(&mut self, policy: Option<OverflowPolicy>) {
+        self.overflow = policy;
+    }
+
+    /// Microcode table driving the instruction cycle
+    pub fn microcode(&self) -> &Microcode {
+        &self.microcode
+    }
+
+    /// Replace the microcode table, e.g. with one loaded from a file
+    ///
+    /// Opcodes 16-30 are only decoded when the profile has extended opcodes.
+    pub fn set_microcode(&mut self, microcode: impl Into<Arc<Microcode>>) {
+        self.microcode = microcode.into();
+    }
+
+    /// Tick-by-tick or instruction-level execution
+    pub const fn execution_mode(&self) -> ExecutionMode {
+        self.mode
+    }
+
+    /// Select how instructions are carried out
+    ///
+    /// Both modes give the same registers, memory and I/O at instruction boundaries;
+    /// [`ExecutionMode::Fast`] just gets there with less work.
+    pub const fn set_execution_mode(&mut self, mode: ExecutionMode) {
+        self.mode = mode;
+    }
+
+    /// Capture the complete machine state
+    ///
+    /// Attached devices, the microcode table and the execution mode are not part of
+    /// the snapshot. Only the addresses of enabled, permanent breakpoints are kept;
+    /// their conditions and counts are not. A running machine is saved as paused
+    /// from the console.
+    pub fn save_state(&self) -> Snapshot {
+        Snapshot {
+            profile: self.profile,
+            registers: self.registers(),
+            run_state: match &self.run_state {
+                RunState::Running => RunState::Paused(PauseReason::Console),
+                run_state => run_state.clone(),
+            },
+            breakpoints: self
+                .breakpoints
+                .iter()
+                .filter(|b| b.enabled && !b.temporary)
+                .map(|b| b.address)
+                .collect(),
+            ram: Box::new(self.ram),
+        }
+    }
+
+    /// Restore a state captured by [`save_state`](Self::save_state), run state
+    /// included, so a halted or faulted machine stays stopped
+    pub fn load_state(&mut self, snapshot: &Snapshot) {
+        self.profile = snapshot.profile;
+        self.restore_registers(&snapshot.registers);
+        self.run_state = snapshot.run_state.clone();
+        self.breakpoints.clear();
+        for &addr in &snapshot.breakpoints {
+            self.breakpoints.add(addr);
+        }
+        self.ram = *snapshot.ram;
+    }
+
+    /// Everything but RAM, breakpoints and configuration
+    const fn registers(&self) -> Registers {
+        Registers {
+            execute: matches!(self.state, State::Execute),
+            clock_pulse: self.clock_pulse,
+            power: matches!(self.run_state, RunState::Running | RunState::Paused(_)),
+            transfer_active: self.io.transfer_active,
+            ready: self.io.ready,
+            pc: self.pc,
+            a: self.a,
+            z: self.z,
+            sr: self.sr,
+            mar: self.mar,
+            mbr: self.mbr,
+            ir: self.ir,
+            dsl: self.dsl,
+            dil: self.dil,
+            dol: self.dol,
+            flags: self.flags,
+            interrupts: self.interrupts,
+            ipc: self.ipc,
+        }
+    }
+
+    /// Put back registers taken by [`registers`](Self::registers)
+    ///
+    /// Only whether the clock was running is kept, so a running machine comes back
+    /// paused and a stopped one powered off.
+    fn restore_registers(&mut self, registers: &Registers) {
+        self.state = if registers.execute {
+            State::Execute
+        } else {
+            State::Fetch
+        };
+        self.clock_pulse = registers.clock_pulse;
+        self.run_state = if registers.power {
+            RunState::Paused(PauseReason::Console)
+        } else {
+            RunState::PoweredOff
+        };
+        self.io.transfer_active = registers.transfer_active;
+        self.io.ready = registers.ready;
+        self.pc = registers.pc;
+        self.a = registers.a;
+        self.z = registers.z;
+        self.sr = registers.sr;
+        self.mar = registers.mar;
+        self.mbr = registers.mbr;
+        self.ir = registers.ir;
+        self.dsl = registers.dsl;
+        self.dil = registers.dil;
+        self.dol = registers.dol;
+        self.flags = registers.flags;
+        self.interrupts = registers.interrupts;
+        self.ipc = registers.ipc;
+        self.watch_hit = None;
+        self.bus.end_transfer();
+    }
+
+    /// Print the registers after every cycle of the interactive console (on by
+    /// default)
+    pub const fn set_register_trace(&mut self, enabled: bool) {
+        self.debug.print_registers = enabled;
+    }
+
+    /// Record a [`MicroEvent`] for every micro-operation the sequencer reaches
+    ///
+    /// Fast mode is not used while explain mode is on. Events collect until taken
+    /// with [`Self::take_micro_events`]; turning the mode off discards them.
+    pub fn set_explain_mode(&mut self, enabled: bool) {
+        self.explain = enabled.then(Vec::new);
+    }
+
+    /// Whether explain mode is on
+    pub const fn explain_mode(&self) -> bool {
+        self.explain.is_some()
+    }
+
+    /// The micro-operations run since the last call, oldest first
+    pub fn take_micro_events(&mut self) -> Vec<MicroEvent> {
+        self.explain.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// History recorded for reverse execution
+    pub const fn history(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Keep the last `depth` instructions or ticks for reverse execution
+    ///
+    /// A depth of 0, the default, records nothing. Changing the setting discards the
+    /// history recorded so far. With [`Granularity::Tick`] fast mode is not used, so
+    /// every tick can be undone.
+    pub fn set_history(&mut self, depth: usize, granularity: Granularity) {
+        self.journal = Journal::new(depth, granularity);
+    }
+
+    /// Undo the most recent journal entry, one tick or one instruction
+    ///
+    /// Returns `false` when there is no history left. I/O devices are not rewound.
+    pub fn step_back(&mut self) -> bool {
+        let Some(entry) = self.journal.pop() else {
+            return false;
+        };
+        for write in entry.writes.iter().rev() {
+            self.ram[usize::from(write.address)] = write.old;
+        }
+        self.restore_registers(&entry.before);
+        true
+    }
+
+    /// Undo back to the start of the previous instruction
+    ///
+    /// Returns `false` when there is no history left.
+    pub fn reverse_step(&mut self) -> bool {
+        if !self.step_back() {
+            return false;
+        }
+        while (self.state != State::Fetch || self.clock_pulse != 0) && self.step_back() {}
+        true
+    }
+
+    /// Run backwards until the next instruction to fetch is at a breakpoint
+    ///
+    /// Returns the breakpoint reached, or `None` once the history runs out.
+    pub fn reverse_continue(&mut self) -> Option<BlueRegister> {
+        while self.reverse_step() {
+            if self.state == State::Fetch
+                && self.clock_pulse == 0
+                && self.breakpoints.matches(self.pc, self)
+            {
+                return Some(self.pc);
+            }
+        }
+        None
     }
 
     /// Get the current instruction from the IR
-    fn get_instruction(&self) -> Instruction {
-        ((self.ir & 0xF000) >> 12).try_into().unwrap()
+    fn get_instruction(&self) -> Result<Instruction, FaultKind> {
+        Instruction::decode(self.ir, self.profile).map_err(|_| FaultKind::InvalidOpcode(self.ir))
+    }
+
+    /// Build a fault report for the instruction currently in the IR
+    const fn fault(&self, kind: FaultKind) -> Fault {
+        Fault {
+            kind,
+            pc: self.pc,
+            ir: self.ir,
+        }
+    }
+
+    /// Read the word addressed by MAR
+    fn read_ram(&mut self) -> Result<u16, FaultKind> {
+        let word = self
+            .read_memory(self.mar)
+            .ok_or(FaultKind::AddressOutOfRange(self.mar))?;
+        self.watch(self.mar, word, word, false);
+        Ok(word)
+    }
+
+    /// Write `value` to the word addressed by MAR
+    fn write_ram(&mut self, value: u16) -> Result<(), FaultKind> {
+        if let Some(old) = self.read_memory(self.mar) {
+            self.journal.record_write(self.mar, old, value);
+            self.watch(self.mar, old, value, true);
+        }
+        if self.write_memory(self.mar, value) {
+            Ok(())
+        } else {
+            Err(FaultKind::AddressOutOfRange(self.mar))
+        }
+    }
+
+    /// Check a memory access against the watchpoints, keeping the first hit of the
+    /// current instruction
+    fn watch(&mut self, address: BlueRegister, old: u16, new: u16, write: bool) {
+        if self.watch_hit.is_some() {
+            return;
+        }
+        if let Some(id) = self.breakpoints.watch_hit(address, write) {
+            self.watch_hit = Some(WatchHit {
+                id,
+                instruction: self.instruction,
+                address,
+                write,
+                old,
+                new,
+            });
+        }
+    }
+
+    /// Pause for a watchpoint hit once the instruction that made it has finished
+    fn watch_stop(&mut self) -> Option<StopReason> {
+        if self.state != State::Fetch || self.clock_pulse != 0 {
+            return None;
+        }
+        let hit = self.watch_hit.take()?;
+        self.pause(PauseReason::Watchpoint(hit));
+        Some(StopReason::Watchpoint(hit))
+    }
+
+    /// Pause before the next tick if an enabled tick breakpoint is set on it
+    fn tick_stop(&mut self) -> Option<StopReason> {
+        if !self.is_running() || !self.breakpoints.has_tick_breakpoints() {
+            return None;
+        }
+        let tick = self.next_tick()?;
+        self.breakpoints.tick_hit(tick)?;
+        self.pause(PauseReason::TickBreakpoint(tick));
+        Some(StopReason::TickBreakpoint(tick))
     }
 
     /// Update processor flags based on operation results
     const fn set_flags(&mut self, result: BlueRegister, carry: bool, overflow: bool) {
+        if !self.profile.has_flags() {
+            return;
+        }
         self.flags = 0;
 
         if result == 0 {
@@ -216,562 +1377,624 @@ impl BlueComputer {
         }
     }
 
-    // Instruction implementations
-    // Each follows the 8-step cycle with state-specific behavior
-
-    /// HLT instruction - halt the processor
-    const fn do_hlt(&mut self, tick: u8) {
-        match tick {
-            6 => self.power = false,
-            7 => self.mar = self.pc,
-            _ => (),
+    /// Set flags after ADD or SUB and apply the overflow policy
+    fn overflow(
+        &mut self,
+        result: BlueRegister,
+        carry: bool,
+        overflow: bool,
+    ) -> Result<(), FaultKind> {
+        let policy = self.overflow_policy();
+        self.set_flags(
+            result,
+            carry,
+            overflow && !matches!(policy, OverflowPolicy::Wrap),
+        );
+        if !overflow {
+            return Ok(());
         }
-    }
-
-    /// ADD instruction - add memory to accumulator
-    fn do_add(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => {
-                    self.mar = self.ir & 0x0FFF;
-                    self.state = State::Execute;
-                }
-                _ => (),
-            },
-            State::Execute => match tick {
-                2 => {
-                    self.a = 0;
-                    self.mbr = 0;
-                }
-                3 => self.mbr = self.ram[self.mar as usize],
-                6 => {
-                    let z = u32::from(self.z);
-                    let m = u32::from(self.mbr);
-                    let result = z + m;
-
-                    self.a = u16::try_from(result).unwrap();
-
-                    let z_s = i32::from(self.z);
-                    let m_s = i32::from(self.mbr);
-                    let result_s = z_s.wrapping_add(m_s);
-                    let overflow = ((z_s ^ result_s) & 0x8000 != 0) && ((z_s ^ m_s) & 0x8000 == 0);
-                    let carry = result > 0xFFFF;
-
-                    self.set_flags(self.a, carry, overflow);
-                    if overflow {
-                        self.power = false;
-                    }
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+        match policy {
+            OverflowPolicy::Halt => self.run_state = RunState::Halted(HaltReason::Overflow),
+            OverflowPolicy::Trap(handler) => {
+                // The instruction ends with MAR<-PC, so the handler is fetched next
+                self.ipc = self.pc;
+                self.pc = handler;
+                self.interrupts = false;
+            }
+            OverflowPolicy::Fault => return Err(FaultKind::Overflow),
+            OverflowPolicy::Flag | OverflowPolicy::Wrap => (),
         }
+        Ok(())
     }
 
-    /// XOR instruction - bitwise exclusive OR
-    const fn do_xor(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => {
-                    self.mar = self.ir & 0x0FFF;
-                    self.state = State::Execute;
-                }
-                _ => (),
-            },
-            State::Execute => match tick {
-                2 => {
-                    self.a = 0;
-                    self.mbr = 0;
-                }
-                3 => self.mbr = self.ram[self.mar as usize],
-                6 => {
-                    self.a = self.z ^ self.mbr;
-                    self.set_flags(self.a, false, false);
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+    /// Why a stopped processor cannot be stepped, or `None` if it can
+    ///
+    /// Stepping a paused machine resumes it.
+    fn stop_reason(&self) -> Option<StopReason> {
+        match &self.run_state {
+            RunState::Running | RunState::Paused(_) => None,
+            RunState::Halted(HaltReason::Overflow) => Some(StopReason::Overflow),
+            RunState::Halted(_) | RunState::PoweredOff => Some(StopReason::Halted),
+            RunState::Faulted(fault) => Some(StopReason::Fault(fault.clone())),
         }
     }
 
-    /// AND instruction - bitwise AND
-    const fn do_and(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => {
-                    self.mar = self.ir & 0x0FFF;
-                    self.state = State::Execute;
-                }
-                _ => (),
-            },
-            State::Execute => match tick {
-                2 => {
-                    self.a = 0;
-                    self.mbr = 0;
-                }
-                3 => self.mbr = self.ram[self.mar as usize],
-                6 => {
-                    self.a = self.z & self.mbr;
-                    self.set_flags(self.a, false, false);
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+    /// Operand field of the IR, 8 bits for extended instructions and 12 otherwise
+    const fn operand(&self) -> BlueRegister {
+        if Instruction::opcode_of(self.ir, self.profile) > EXTENDED_OPCODE as u8 {
+            self.ir & 0x00FF
+        } else {
+            self.ir & 0x0FFF
         }
     }
 
-    /// IOR instruction - bitwise inclusive OR
-    const fn do_ior(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => {
-                    self.mar = self.ir & 0x0FFF;
-                    self.state = State::Execute;
-                }
-                _ => (),
-            },
-            State::Execute => match tick {
-                2 => {
-                    self.a = 0;
-                    self.mbr = 0;
-                }
-                3 => self.mbr = self.ram[self.mar as usize],
-                6 => {
-                    self.a = self.z | self.mbr;
-                    self.set_flags(self.a, false, false);
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+    /// Whether a micro-operation guard holds
+    const fn condition_holds(&self, condition: Condition) -> bool {
+        match condition {
+            Condition::Negative => self.a & 0x8000 != 0,
+            Condition::Ready => self.io.ready,
+            Condition::Idle => !self.io.transfer_active,
+            Condition::Zero => self.flags & FLAG_ZERO != 0,
+            Condition::Carry => self.flags & FLAG_CARRY != 0,
+            Condition::Overflow => self.flags & FLAG_OVERFLOW != 0,
         }
     }
 
-    /// NOT instruction - bitwise complement
-    const fn do_not(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => self.state = State::Execute,
-                _ => (),
-            },
-            State::Execute => match tick {
-                0 => self.a = 0,
-                1 => self.a = !self.z,
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+    /// Perform a single register transfer
+    fn run_micro_op(&mut self, op: MicroOp) -> Result<(), FaultKind> {
+        match op {
+            MicroOp::IncrementPc => self.pc = self.pc.wrapping_add(1),
+            MicroOp::ClearPc => self.pc = 0,
+            MicroOp::PcFromIr => self.pc = self.operand(),
+            MicroOp::PcFromMar => self.pc = self.mar,
+            MicroOp::MarFromPc => self.mar = self.pc,
+            MicroOp::MarFromIr => self.mar = self.operand(),
+            MicroOp::ClearMbr => self.mbr = 0,
+            MicroOp::ReadMemory => self.mbr = self.read_ram()?,
+            MicroOp::WriteMemory => self.write_ram(self.mbr)?,
+            MicroOp::MbrFromA => self.mbr = self.a,
+            MicroOp::ClearIr => self.ir = 0,
+            MicroOp::IrFromMbr => self.ir = self.mbr,
+            MicroOp::ClearZ => self.z = 0,
+            MicroOp::ZFromA => self.z = self.a,
+            MicroOp::ClearA => self.a = 0,
+            MicroOp::AFromMbr => self.a = self.mbr,
+            MicroOp::AFromPc => self.a = self.pc & 0x0FFF,
+            MicroOp::AFromSr => self.a = self.sr,
+            MicroOp::AFromDil => self.a = (self.dil << 8) & 0xFF00,
+            MicroOp::Add => {
+                let (result, carry) = self.z.overflowing_add(self.mbr);
+                self.a = result;
+
+                let overflow =
+                    ((self.z ^ result) & 0x8000 != 0) && ((self.z ^ self.mbr) & 0x8000 == 0);
+
+                self.overflow(result, carry, overflow)?;
+            }
+            MicroOp::Subtract => {
+                let (result, carry) = self.z.overflowing_sub(self.mbr);
+                self.a = result;
+
+                let overflow =
+                    ((self.z ^ self.mbr) & 0x8000 != 0) && ((self.z ^ result) & 0x8000 != 0);
+
+                self.overflow(result, carry, overflow)?;
+            }
+            MicroOp::Compare => {
+                let (result, carry) = self.z.overflowing_sub(self.mbr);
+
+                let overflow =
+                    ((self.z ^ self.mbr) & 0x8000 != 0) && ((self.z ^ result) & 0x8000 != 0);
+
+                self.set_flags(result, carry, overflow);
+            }
+            MicroOp::Xor => {
+                self.a = self.z ^ self.mbr;
+                self.set_flags(self.a, false, false);
+            }
+            MicroOp::And => {
+                self.a = self.z & self.mbr;
+                self.set_flags(self.a, false, false);
+            }
+            MicroOp::Ior => {
+                self.a = self.z | self.mbr;
+                self.set_flags(self.a, false, false);
+            }
+            MicroOp::Not => self.a = !self.z,
+            MicroOp::RotateLeft => self.a = self.z.rotate_left(1),
+            MicroOp::DslFromIr => self.dsl = self.ir & 0x003F,
+            MicroOp::DolFromA => self.dol = (self.a >> 8) & 0x00FF,
+            MicroOp::StartTransfer => self.io.transfer_active = true,
+            MicroOp::EndTransfer => self.io.transfer_active = false,
+            MicroOp::Execute => self.state = State::Execute,
+            MicroOp::Fetch => self.state = State::Fetch,
+            MicroOp::Halt => self.run_state = RunState::Halted(HaltReason::Instruction),
+            MicroOp::EnableInterrupts => self.interrupts = true,
+            MicroOp::DisableInterrupts => self.interrupts = false,
+            MicroOp::PcFromIpc => self.pc = self.ipc,
         }
+        Ok(())
     }
 
-    /// LDA instruction - load accumulator from memory
-    const fn do_lda(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => {
-                if tick == 7 {
-                    self.state = State::Execute;
-                    self.mar = self.ir & 0x0FFF;
-                }
+    /// Run the guarded steps of one microcode cell, recording them as part of
+    /// `tick` if given
+    fn run_steps(&mut self, steps: &[MicroStep], tick: Option<MicroTick>) -> Result<(), FaultKind> {
+        for step in steps {
+            let performed = step.condition.is_none_or(|c| self.condition_holds(c));
+            let (address, flags) = (self.mar, self.flags);
+            if performed {
+                self.run_micro_op(step.op)?;
             }
-            State::Execute => match tick {
-                1 => self.a = 0,
-                2 => self.mbr = 0,
-                4 => {
-                    self.a = self.ram[self.mar as usize];
-                    self.mbr = self.a;
+            if let Some(tick) = tick {
+                let event = MicroEvent {
+                    tick,
+                    op: step.op,
+                    condition: step.condition,
+                    performed,
+                    address: matches!(step.op, MicroOp::ReadMemory | MicroOp::WriteMemory)
+                        .then_some(address),
+                    value: self.destination(step.op),
+                    flags_set: self.flags & !flags,
+                    flags_cleared: flags & !self.flags,
+                };
+                if let Some(events) = &mut self.explain {
+                    events.push(event);
                 }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+            }
         }
+        Ok(())
     }
 
-    /// STA instruction - store accumulator to memory
-    const fn do_sta(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => {
-                if tick == 7 {
-                    self.state = State::Execute;
-                    self.mar = self.ir & 0x0FFF;
-                }
+    /// Value now in the register or memory word `op` stores to
+    const fn destination(&self, op: MicroOp) -> Option<u16> {
+        match op {
+            MicroOp::IncrementPc
+            | MicroOp::ClearPc
+            | MicroOp::PcFromIr
+            | MicroOp::PcFromMar
+            | MicroOp::PcFromIpc => Some(self.pc),
+            MicroOp::MarFromPc | MicroOp::MarFromIr => Some(self.mar),
+            MicroOp::ClearMbr | MicroOp::ReadMemory | MicroOp::WriteMemory | MicroOp::MbrFromA => {
+                Some(self.mbr)
             }
-            State::Execute => match tick {
-                3 => self.mbr = 0,
-                4 => {
-                    self.ram[self.mar as usize] = self.a;
-                    self.mbr = self.a;
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+            MicroOp::ClearIr | MicroOp::IrFromMbr => Some(self.ir),
+            MicroOp::ClearZ | MicroOp::ZFromA => Some(self.z),
+            MicroOp::ClearA
+            | MicroOp::AFromMbr
+            | MicroOp::AFromPc
+            | MicroOp::AFromSr
+            | MicroOp::AFromDil
+            | MicroOp::Add
+            | MicroOp::Subtract
+            | MicroOp::Xor
+            | MicroOp::And
+            | MicroOp::Ior
+            | MicroOp::Not
+            | MicroOp::RotateLeft => Some(self.a),
+            MicroOp::DslFromIr => Some(self.dsl),
+            MicroOp::DolFromA => Some(self.dol),
+            MicroOp::Compare
+            | MicroOp::StartTransfer
+            | MicroOp::EndTransfer
+            | MicroOp::Execute
+            | MicroOp::Fetch
+            | MicroOp::Halt
+            | MicroOp::EnableInterrupts
+            | MicroOp::DisableInterrupts => None,
         }
     }
 
-    /// SRJ instruction - subroutine jump
-    const fn do_srj(&mut self, tick: u8) {
-        match tick {
-            5 => self.a = self.pc & 0x0FFF,
-            6 => self.pc = 0,
-            7 => {
-                self.mar = self.ir & 0x0FFF;
-                self.pc = self.mar;
+    /// Process a single clock tick (0-7)
+    fn process_tick(&mut self, tick: u8) -> Result<(), Fault> {
+        self.execute_tick(tick).map_err(|kind| self.fault(kind))
+    }
+
+    /// Sequence the microcode for one tick: the common steps, then the routine of
+    /// the instruction in the IR
+    fn execute_tick(&mut self, tick: u8) -> Result<(), FaultKind> {
+        fn cell(routine: &Routine, state: State, tick: usize) -> &[MicroStep] {
+            match state {
+                State::Fetch => &routine.fetch[tick],
+                State::Execute => &routine.execute[tick],
             }
-            _ => (),
         }
+
+        let microcode = Arc::clone(&self.microcode);
+        let at = self.explain.as_ref().and_then(|_| self.next_tick());
+        let tick = usize::from(tick);
+        let state = self.state;
+
+        self.run_steps(cell(microcode.common(), state, tick), at)?;
+        let opcode = Instruction::opcode_of(self.ir, self.profile);
+        let routine = microcode
+            .routine(opcode)
+            .ok_or(FaultKind::InvalidOpcode(self.ir))?;
+        self.run_steps(cell(routine, state, tick), at)
     }
 
-    /// JMA instruction - jump if accumulator negative
-    const fn do_jma(&mut self, tick: u8) {
-        match tick {
-            5 => {
-                if (self.a & 0x8000) != 0 {
-                    self.pc = 0;
-                }
-            }
-            6 => {
-                if (self.a & 0x8000) != 0 {
-                    self.pc = self.ir & 0x0FFF;
-                }
+    /// The I/O transfer currently waiting for the host, if any
+    ///
+    /// Transfers handled by attached devices are serviced by the bus and only
+    /// reported here when INP finds the device out of input under
+    /// [`EndOfInputPolicy::Wait`].
+    pub fn pending_io(&self) -> Option<IoRequest> {
+        if !self.io.transfer_active || self.io.ready {
+            return None;
+        }
+        let device = (self.dsl & 0x003F) as u8;
+        let waiting_for_input =
+            self.bus.at_end(device) && self.bus.end_of_input_policy() == EndOfInputPolicy::Wait;
+        match self.get_instruction() {
+            Ok(Instruction::Inp) if !self.bus.handles(device, true) || waiting_for_input => {
+                Some(IoRequest::Input { device })
             }
-            7 => self.mar = self.pc,
-            _ => (),
+            Ok(Instruction::Out) if !self.bus.handles(device, false) => Some(IoRequest::Output {
+                device,
+                byte: (self.dol & 0x00FF) as u8,
+            }),
+            _ => None,
         }
     }
 
-    /// JMP instruction - unconditional jump
-    const fn do_jmp(&mut self, tick: u8) {
-        match tick {
-            5 => self.pc = 0,
-            6 => self.pc = self.ir & 0x0FFF,
-            7 => self.mar = self.pc,
-            _ => (),
-        }
+    /// Complete a pending INP transfer with `byte`
+    pub fn complete_input(&mut self, byte: u8) {
+        self.dil = BlueRegister::from(byte);
+        self.io.ready = true;
+        self.bus.end_transfer();
     }
 
-    /// INP instruction - input from device
-    const fn do_inp(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => {
-                    self.a = 0;
-                    self.dsl = self.ir & 0x003F;
-                }
-                6 => self.io.transfer_active = true,
-                7 => self.state = State::Execute,
-                _ => (),
-            },
-            State::Execute => match tick {
-                4 => {
-                    if self.io.ready {
-                        self.a = (self.dil << 8) & 0xFF00;
-                    }
-                }
-                5 => {
-                    if self.io.ready {
-                        self.io.transfer_active = false;
-                    }
-                }
-                7 => {
-                    if !self.io.transfer_active {
-                        self.state = State::Fetch;
-                        self.mar = self.pc;
-                    }
-                }
-                _ => (),
-            },
-        }
+    /// Complete a pending OUT transfer, acknowledging the byte in DOL
+    pub const fn complete_output(&mut self) {
+        self.io.ready = true;
+        self.bus.end_transfer();
     }
 
-    /// OUT instruction - output to device
-    const fn do_out(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => {
-                    self.dol = (self.a >> 8) & 0x00FF;
-                    self.dsl = self.ir & 0x003F;
-                }
-                6 => self.io.transfer_active = true,
-                7 => self.state = State::Execute,
-                _ => (),
-            },
-            State::Execute => match tick {
-                4 => {
-                    if self.io.ready {
-                        self.io.transfer_active = false;
-                    }
-                }
-                7 => {
-                    if !self.io.transfer_active {
-                        self.state = State::Fetch;
-                        self.mar = self.pc;
-                    }
+    /// Route an active transfer to the device selected by DSL
+    fn service_bus(&mut self) -> Result<(), FaultKind> {
+        if !self.io.transfer_active || self.io.ready {
+            return Ok(());
+        }
+        let input = match self.get_instruction() {
+            Ok(Instruction::Inp) => true,
+            Ok(Instruction::Out) => false,
+            _ => return Ok(()),
+        };
+        let selector = (self.dsl & 0x003F) as u8;
+        if input && self.bus.at_end(selector) {
+            return match self.bus.end_of_input_policy() {
+                EndOfInputPolicy::Wait => Ok(()),
+                EndOfInputPolicy::Halt => {
+                    self.run_state = RunState::Halted(HaltReason::EndOfInput);
+                    Ok(())
                 }
-                _ => (),
-            },
+                EndOfInputPolicy::Fault => Err(FaultKind::EndOfInput(selector)),
+            };
+        }
+        if self.bus.handles(selector, input) && !self.bus.transfer_due(selector, input) {
+            return Ok(());
+        }
+        let Some(device) = self.bus.get_mut(selector, input) else {
+            return match self.bus.unattached_policy() {
+                UnattachedPolicy::Wait => Ok(()),
+                UnattachedPolicy::Fault => Err(FaultKind::UnattachedDevice(selector)),
+            };
+        };
+        if !device.ready(input) {
+            return Ok(());
         }
-    }
 
-    /// RAL instruction - rotate accumulator left
-    const fn do_ral(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => self.state = State::Execute,
-                _ => (),
-            },
-            State::Execute => match tick {
-                0 => self.a = 0,
-                1 => self.a = ((self.z & 0x8000) >> 15) | (self.z << 1),
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+        let io_fault = |e: io::Error| FaultKind::Io(e.to_string());
+        if input {
+            let byte = device.read_byte().map_err(io_fault)?;
+            self.complete_input(byte);
+        } else {
+            device
+                .write_byte((self.dol & 0x00FF) as u8)
+                .map_err(io_fault)?;
+            self.complete_output();
         }
+        Ok(())
     }
 
-    /// CSA instruction - copy switch register to accumulator
-    const fn do_csa(&mut self, tick: u8) {
-        match tick {
-            5 => self.a = 0,
-            6 => self.a = self.sr,
-            7 => self.mar = self.pc,
-            _ => (),
+    /// Process the current tick, finishing the cycle after tick 7
+    fn advance_tick(&mut self) -> Result<(), Fault> {
+        if self.state == State::Fetch && self.clock_pulse == 0 {
+            self.instruction = self.mar;
+            self.watch_hit = None;
+        }
+        if self
+            .journal
+            .starts_entry(self.state == State::Execute, self.clock_pulse)
+        {
+            self.journal.begin(self.registers());
+        }
+        self.process_tick(self.clock_pulse)?;
+        self.bus.clock();
+        self.clock_pulse += 1;
+        if self.clock_pulse < 8 {
+            return Ok(());
+        }
+        self.clock_pulse = 0;
+
+        if !self.io.transfer_active {
+            self.io.ready = false;
         }
+        self.service_bus().map_err(|kind| self.fault(kind))?;
+        self.take_interrupt();
+        Ok(())
     }
 
-    /// NOP instruction - no operation
-    const fn do_nop(&mut self, tick: u8) {
-        if tick == 7 {
-            self.mar = self.pc;
+    /// Vector to the interrupt handler if a device is requesting service and the
+    /// processor is between instructions with interrupts enabled
+    ///
+    /// Interrupts only exist in profiles with the extended instructions, which
+    /// provide EIN, DIN and RTI.
+    fn take_interrupt(&mut self) {
+        if !self.interrupts
+            || !self.is_running()
+            || self.state != State::Fetch
+            || self.io.transfer_active
+            || !self.profile.has_extended_opcodes()
+            || self.bus.pending_interrupt().is_none()
+        {
+            return;
         }
+        self.ipc = self.pc;
+        self.pc = INTERRUPT_VECTOR;
+        self.mar = INTERRUPT_VECTOR;
+        self.interrupts = false;
     }
 
-    /// SUB instruction - subtract memory from accumulator (extension)
-    fn do_sub(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => {
-                    self.mar = self.ir & 0x0FFF;
-                    self.state = State::Execute;
-                }
-                _ => (),
-            },
-            State::Execute => match tick {
-                2 => {
-                    self.a = 0;
-                    self.mbr = 0;
+    /// Service I/O transfers from the console
+    fn handle_io(&mut self) -> Result<(), Fault> {
+        match self.pending_io() {
+            Some(IoRequest::Input { device }) => {
+                while self.debug.manual_input && !self.io.ready {
+                    println!("Input byte: ");
+                    let mut input = String::new();
+                    let read = io::stdin()
+                        .read_line(&mut input)
+                        .map_err(|e| self.fault(FaultKind::Io(e.to_string())))?;
+                    if read == 0 {
+                        return Err(self.fault(FaultKind::EndOfInput(device)));
+                    }
+                    if let Ok(input_byte) = u8::from_str_radix(input.trim(), 16) {
+                        self.complete_input(input_byte);
+                    } else {
+                        println!("Invalid input. Try again");
+                    }
                 }
-                3 => self.mbr = self.ram[self.mar as usize],
-                6 => {
-                    let z = i32::from(self.z);
-                    let m = i32::from(self.mbr);
-                    let result = z.wrapping_sub(m);
-                    self.a = u16::try_from(result).unwrap();
-
-                    let carry = (z as u32) < (m as u32);
-                    let overflow = ((z ^ m) & 0x8000 != 0) && ((z ^ result) & 0x8000 != 0);
+            }
+            Some(IoRequest::Output { byte, .. }) if self.debug.enabled => {
+                println!("{byte:02x} .");
+                self.complete_output();
+            }
+            _ => (),
+        }
+        Ok(())
+    }
 
-                    self.set_flags(self.a, carry, overflow);
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+    /// Execute the rest of the 8-tick cycle, stopping early before a tick breakpoint
+    fn emulate_cycle(&mut self) -> Result<(), Fault> {
+        loop {
+            self.advance_tick()?;
+            if self.clock_pulse == 0 || self.tick_stop().is_some() {
+                return Ok(());
+            }
         }
     }
 
-    /// CMP instruction - compare memory with accumulator (extension)
-    fn do_cmp(&mut self, tick: u8) {
-        match self.state {
-            State::Fetch => match tick {
-                5 => self.z = 0,
-                6 => self.z = self.a,
-                7 => {
-                    self.mar = self.ir & 0x0FFF;
-                    self.state = State::Execute;
-                }
-                _ => (),
-            },
-            State::Execute => match tick {
-                3 => self.mbr = self.ram[self.mar as usize],
-                6 => {
-                    let z = i32::from(self.z);
-                    let m = i32::from(self.mbr);
-                    let result = u16::try_from(z.wrapping_sub(m)).unwrap();
-
-                    let carry = (z as u32) < (m as u32);
-                    let overflow =
-                        ((z ^ m) & 0x8000 != 0) && ((z ^ i32::from(result)) & 0x8000 != 0);
-
-                    self.set_flags(result, carry, overflow);
-                }
-                7 => {
-                    self.mar = self.pc;
-                    self.state = State::Fetch;
-                }
-                _ => (),
-            },
+    /// Advance the machine by a single clock tick
+    ///
+    /// Returns `None` while the machine keeps running. Stop conditions other than
+    /// faults and tick breakpoints are only reported once the current cycle
+    /// completes. A tick breakpoint stops the machine before its tick runs, but is
+    /// only checked after a tick, so a machine sitting on one runs that tick first.
+    pub fn step_tick(&mut self) -> Option<StopReason> {
+        if let RunState::Paused(_) = self.run_state {
+            self.run_state = RunState::Running;
+        }
+        if let RunState::Faulted(fault) = &self.run_state {
+            return Some(StopReason::Fault(fault.clone()));
+        }
+        if self.clock_pulse == 0
+            && let Some(stop) = self.stop_reason()
+        {
+            return Some(stop);
+        }
+        if let Err(e) = self.advance_tick() {
+            self.run_state = RunState::Faulted(e.clone());
+            return Some(StopReason::Fault(e));
+        }
+        if self.clock_pulse != 0 {
+            return self.tick_stop();
+        }
+        if let Some(stop) = self
+            .stop_reason()
+            .or_else(|| self.watch_stop())
+            .or_else(|| self.tick_stop())
+        {
+            Some(stop)
+        } else if self.pending_io().is_some() {
+            Some(StopReason::WaitingForIo)
+        } else {
+            None
         }
     }
 
-    /// Process a single clock tick (0-7)
-    fn process_tick(&mut self, tick: u8) {
-        // Common fetch cycle operations
-        match tick {
-            2 => {
-                if self.state == State::Fetch {
-                    self.pc += 1;
-                }
+    /// Execute the next instruction in one go, if fast mode allows it
+    ///
+    /// Returns the number of cycles the instruction took, or `None` when it has to
+    /// be sequenced tick by tick: mid-instruction, custom microcode, tick
+    /// breakpoints, explain mode, INP/OUT, ADD/SUB under the `fault` overflow policy, a fetch
+    /// that would fault, or a two-cycle instruction with `budget` below two.
+    fn step_fast(&mut self, budget: u64) -> Option<u64> {
+        if let RunState::Paused(_) = self.run_state {
+            self.run_state = RunState::Running;
+        }
+        if self.mode != ExecutionMode::Fast
+            || !self.is_running()
+            || self.state != State::Fetch
+            || self.clock_pulse != 0
+            || !Arc::ptr_eq(&self.microcode, &Microcode::builtin())
+            || self.breakpoints.has_tick_breakpoints()
+            || self.explain.is_some()
+            || (self.journal.depth() > 0 && self.journal.granularity() == Granularity::Tick)
+        {
+            return None;
+        }
+        let word = self.read_memory(self.mar)?;
+        let instruction = Instruction::decode(word, self.profile).ok()?;
+        let cycles = match instruction {
+            Instruction::Inp | Instruction::Out => return None,
+            Instruction::Add | Instruction::Sub
+                if self.overflow_policy() == OverflowPolicy::Fault =>
+            {
+                return None;
             }
-            3 => {
-                if self.state == State::Fetch {
-                    self.mbr = 0x00;
-                }
+            Instruction::Hlt
+            | Instruction::Srj
+            | Instruction::Jma
+            | Instruction::Jmp
+            | Instruction::Csa
+            | Instruction::Nop
+            | Instruction::Ein
+            | Instruction::Din
+            | Instruction::Rti
+            | Instruction::Jmz
+            | Instruction::Jmc
+            | Instruction::Jmv => 1,
+            _ => 2,
+        };
+        if cycles > budget {
+            return None;
+        }
+        if self.journal.starts_entry(false, 0) {
+            self.journal.begin(self.registers());
+        }
+        self.instruction = self.mar;
+        self.watch_hit = None;
+        self.watch(self.mar, word, word, false);
+
+        // Common fetch
+        self.pc = self.pc.wrapping_add(1);
+        self.mbr = word;
+        self.ir = word;
+        let operand = instruction.operand(word);
+
+        let alu = match instruction {
+            Instruction::Add => Some(MicroOp::Add),
+            Instruction::Xor => Some(MicroOp::Xor),
+            Instruction::And => Some(MicroOp::And),
+            Instruction::Ior => Some(MicroOp::Ior),
+            Instruction::Sub => Some(MicroOp::Subtract),
+            Instruction::Cmp => Some(MicroOp::Compare),
+            _ => None,
+        };
+        if let Some(op) = alu {
+            self.z = self.a;
+            self.mbr = self.ram[usize::from(operand)];
+            self.watch(operand, self.mbr, self.mbr, false);
+            // ALU operations never touch memory, and ADD/SUB cannot fault here
+            let _ = self.run_micro_op(op);
+        }
+        match instruction {
+            Instruction::Hlt => self.run_state = RunState::Halted(HaltReason::Instruction),
+            Instruction::Not => {
+                self.z = self.a;
+                self.a = !self.z;
             }
-            4 => {
-                if self.state == State::Fetch {
-                    self.ir = 0x00;
-                    self.mbr = self.ram[self.mar as usize];
-                }
+            Instruction::Lda => {
+                self.mbr = self.ram[usize::from(operand)];
+                self.watch(operand, self.mbr, self.mbr, false);
+                self.a = self.mbr;
             }
-            5 => {
-                if self.state == State::Fetch {
-                    self.ir = self.mbr;
-                }
+            Instruction::Sta => {
+                self.mbr = self.a;
+                let old = self.ram[usize::from(operand)];
+                self.journal.record_write(operand, old, self.mbr);
+                self.watch(operand, old, self.mbr, true);
+                self.ram[usize::from(operand)] = self.mbr;
             }
-            _ => (),
-        }
-
-        // Dispatch to current instruction handler
-        match self.get_instruction() {
-            Instruction::Hlt => self.do_hlt(tick),
-            Instruction::Add => self.do_add(tick),
-            Instruction::Xor => self.do_xor(tick),
-            Instruction::And => self.do_and(tick),
-            Instruction::Ior => self.do_ior(tick),
-            Instruction::Not => self.do_not(tick),
-            Instruction::Lda => self.do_lda(tick),
-            Instruction::Sta => self.do_sta(tick),
-            Instruction::Srj => self.do_srj(tick),
-            Instruction::Jma => self.do_jma(tick),
-            Instruction::Jmp => self.do_jmp(tick),
-            Instruction::Inp => self.do_inp(tick),
-            Instruction::Out => self.do_out(tick),
-            Instruction::Ral => self.do_ral(tick),
-            Instruction::Csa => self.do_csa(tick),
-            Instruction::Nop => self.do_nop(tick),
-            Instruction::Sub => self.do_sub(tick),
-            Instruction::Cmp => self.do_cmp(tick),
-        }
-    }
-
-    /// Handle I/O operations based on current instruction
-    fn handle_io(&mut self) {
-        match self.get_instruction() {
-            Instruction::Inp => {
-                if self.io.transfer_active {
-                    while self.debug.enabled && !self.io.ready {
-                        println!("Input byte: ");
-                        let mut input = String::new();
-                        io::stdin().read_line(&mut input).unwrap();
-                        if let Ok(input_byte) = u8::from_str_radix(input.trim(), 16) {
-                            self.dil = BlueRegister::from(input_byte);
-                            self.io.ready = true;
-                        } else {
-                            println!("Invalid input. Try again");
-                        }
-                    }
-                } else {
-                    self.io.ready = false;
-                }
+            Instruction::Srj => {
+                self.a = self.pc & 0x0FFF;
+                self.pc = operand;
             }
-            Instruction::Out => {
-                if self.io.transfer_active {
-                    if self.debug.enabled && !self.io.ready {
-                        println!("{:02x} .", self.dol);
-                        self.io.ready = true;
-                    }
-                } else {
-                    self.io.ready = false;
-                }
+            Instruction::Jma if self.a & 0x8000 != 0 => self.pc = operand,
+            Instruction::Jmp => self.pc = operand,
+            Instruction::Jmz if self.flags & FLAG_ZERO != 0 => self.pc = operand,
+            Instruction::Jmc if self.flags & FLAG_CARRY != 0 => self.pc = operand,
+            Instruction::Jmv if self.flags & FLAG_OVERFLOW != 0 => self.pc = operand,
+            Instruction::Ral => {
+                self.z = self.a;
+                self.a = self.z.rotate_left(1);
             }
-            _ => {
-                self.io.ready = false;
+            Instruction::Csa => self.a = self.sr,
+            Instruction::Ein => self.interrupts = true,
+            Instruction::Din => self.interrupts = false,
+            Instruction::Rti => {
+                self.pc = self.ipc;
+                self.interrupts = true;
             }
+            _ => (),
         }
+        self.mar = self.pc;
+
+        self.io.ready = false;
+        self.bus.clock_ticks(cycles * 8);
+        self.take_interrupt();
+        Some(cycles)
     }
 
-    /// Execute a full 8-tick cycle
-    fn emulate_cycle(&mut self) {
-        while self.clock_pulse < 8 {
-            self.process_tick(self.clock_pulse);
-            self.clock_pulse += 1;
+    /// Run to the end of the current 8-tick cycle
+    fn step_cycle(&mut self) -> Option<StopReason> {
+        loop {
+            let stop = self.step_tick();
+            if stop.is_some() || self.clock_pulse == 0 {
+                return stop;
+            }
         }
-        self.clock_pulse = 0;
     }
 
-    /// Display all register values in hexadecimal
-    fn dump_registers(&self) {
-        println!(
-            "PC: {:04x} A: {:04x} IR: {:04x} Z: {:04x} MAR: {:04x} MBR: {:04x} DSL: {:02x} DIL: {:02x} DOL: {:02x}",
-            self.pc,
-            self.a,
-            self.ir,
-            self.z,
-            self.mar,
-            self.mbr,
-            self.dsl & 0x00FF,
-            self.dil & 0x00FF,
-            self.dol & 0x00FF
-        );
+    /// Run until the current instruction completes its final cycle
+    pub fn step_instruction(&mut self) -> Option<StopReason> {
+        if self.step_fast(u64::MAX).is_some() {
+            return self.stop_reason().or_else(|| self.watch_stop());
+        }
+        loop {
+            if let Some(stop) = self.step_cycle() {
+                return Some(stop);
+            }
+            if self.state == State::Fetch {
+                return None;
+            }
+        }
     }
 
-    /// Display the entire RAM contents
-    fn dump_ram(&self) {
-        println!("==== RAM ====\n0000: ");
-        for (i, word) in self.ram.iter().enumerate() {
-            print!("{word:04x} ");
-            if (i + 1) % 8 == 0 && (i + 1) != RAM_LENGTH {
-                println!("\n{:04x}: ", i + 1);
+    /// Run for at most `limit` cycles
+    ///
+    /// Breakpoints are checked between instructions, so a machine already sitting on
+    /// a breakpoint executes at least one instruction before stopping again.
+    pub fn run_until(&mut self, limit: u64) -> StopReason {
+        let mut cycles = 0;
+        while cycles < limit {
+            if let Some(taken) = self.step_fast(limit - cycles) {
+                cycles += taken;
+                if let Some(stop) = self.stop_reason().or_else(|| self.watch_stop()) {
+                    return stop;
+                }
+            } else {
+                cycles += 1;
+                if let Some(stop) = self.step_cycle() {
+                    return stop;
+                }
+            }
+            if self.state == State::Fetch && self.breakpoint_hit() {
+                self.pause(PauseReason::Breakpoint(self.pc));
+                return StopReason::Breakpoint(self.pc);
             }
         }
-        println!();
+        StopReason::CycleLimit
     }
 
     /// Run a program loaded into memory
@@ -780,74 +2003,109 @@ impl BlueComputer {
     /// * `program` - A slice of 16-bit words containing the program code
     ///
     /// # Example
-    /// ```
+    /// ```no_run
+    /// use blue::BlueComputer;
+    ///
     /// let mut computer = BlueComputer::new();
     /// let program = [0x6010, 0x1011, 0x0000]; // LDA, ADD, HLT
     /// computer.run_program(&program);
     /// ```
     pub fn run_program(&mut self, program: &[u16]) {
         println!("Copying program to the RAM");
-        self.ram.copy_from_slice(&[0; RAM_LENGTH]);
-        self.ram[..program.len()].copy_from_slice(program);
+        self.load_program(program);
+        println!("Pressed ON");
         self.press_on();
+        self.run_interactive();
+    }
 
+    /// Run the interactive console from the current state
+    ///
+    /// A machine that is not running, e.g. one restored from a snapshot, starts at
+    /// the command prompt.
+    pub fn run_interactive(&mut self) {
+        self.run_console(&mut Debugger::new());
+    }
+
+    /// Run the interactive console, taking commands from `debugger`
+    ///
+    /// Commands are read whenever the machine is not running, so commands queued
+    /// on a machine that is paused run before it starts.
+    pub fn run_console(&mut self, debugger: &mut Debugger) {
+        let mut stepping = false;
         loop {
-            self.emulate_cycle();
-            if self.debug.enabled {
-                self.dump_registers();
-                if self.breakpoints.contains(&self.pc) {
-                    println!("Stopped at line {}", self.pc);
-                    self.power = false;
+            if self.is_running() {
+                let cycle = match self.step_fast(u64::MAX) {
+                    Some(_) => Ok(()),
+                    None => self.emulate_cycle(),
+                };
+                for event in self.take_micro_events() {
+                    println!("{event}");
                 }
-
-                while !self.power {
-                    let mut command = String::new();
-                    io::stdin().read_line(&mut command).unwrap();
-                    let command = command.trim();
-
-                    match command {
-                        "c" => self.power = true,
-                        "r" => self.dump_registers(),
-                        "d" => self.dump_ram(),
-                        "q" => {
-                            println!("Stopping...");
+                if let Err(e) = cycle {
+                    println!("Fault: {e}");
+                    debugger::print_registers(self);
+                    self.run_state = RunState::Faulted(e);
+                } else if let RunState::Paused(PauseReason::TickBreakpoint(tick)) = self.run_state {
+                    println!("Stopped before {tick}");
+                } else if self.clock_pulse == 0 {
+                    if self.debug.enabled && self.debug.print_registers {
+                        debugger::print_registers(self);
+                    }
+                    if self.debug.enabled
+                        && self.run_state == RunState::Halted(HaltReason::Overflow)
+                    {
+                        println!("Arithmetic overflow at line {}", self.pc.wrapping_sub(1));
+                    }
+                    if let Some(StopReason::Watchpoint(hit)) = self.watch_stop() {
+                        println!("{hit}");
+                    }
+                    if stepping && self.state == State::Fetch {
+                        stepping = false;
+                        self.pause(PauseReason::Step);
+                    }
+                    if self.debug.enabled
+                        && self.is_running()
+                        && self.state == State::Fetch
+                        && self.breakpoint_hit()
+                    {
+                        println!("Stopped at line {}", self.pc);
+                        self.pause(PauseReason::Breakpoint(self.pc));
+                    }
+                    if let Some(StopReason::TickBreakpoint(tick)) = self.tick_stop() {
+                        println!("Stopped before {tick}");
+                    }
+                }
+            }
+            if !self.debug.enabled && !self.is_running() {
+                return;
+            }
+            if self.debug.enabled {
+                while !self.is_running() {
+                    let line = match debugger.next_line() {
+                        Ok(Some(line)) => line,
+                        Ok(None) => return,
+                        Err(e) => {
+                            println!("Failed to read command: {e}");
                             return;
                         }
-                        "s" => {
-                            self.breakpoints.push(self.pc + 1);
-                            self.power = true;
-                        }
-                        _ => {
-                            if let Some(line) = command
-                                .strip_prefix('b')
-                                .and_then(|s| s.trim().parse().ok())
-                            {
-                                println!("Set breakpoint at line {line}");
-                                self.breakpoints.push(line);
-                            } else if let Some(stripped) = command.strip_prefix('x') {
-                                let parts: Vec<&str> = stripped.split_whitespace().collect();
-                                if parts.len() == 2 {
-                                    if let Ok(val) = parts[1].parse::<BlueRegister>() {
-                                        match parts[0] {
-                                            "PC" => self.pc = val,
-                                            "A" => self.a = val,
-                                            "Z" => self.z = val,
-                                            "SR" => self.sr = val,
-                                            "MAR" => self.mar = val,
-                                            "MBR" => self.mbr = val,
-                                            "IR" => self.ir = val,
-                                            "DSL" => self.dsl = val,
-                                            "DIL" => self.dil = val,
-                                            _ => println!("Invalid register name"),
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                    };
+                    match debugger.run_line(self, &line) {
+                        Flow::Quit => return,
+                        Flow::Step => stepping = true,
+                        Flow::Run | Flow::Prompt => (),
                     }
                 }
             }
-            self.handle_io();
+            if let Err(e) = self.handle_io() {
+                println!("Fault: {e}");
+                return;
+            }
         }
     }
 }
+
+impl Default for BlueComputer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
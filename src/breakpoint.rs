@@ -0,0 +1,566 @@
+//! # Breakpoints
+//!
+//! Breakpoints stop the processor before it fetches the instruction at their
+//! address. Each has an id for the console to refer to it by, can be disabled
+//! without losing it, and counts its hits. A breakpoint can also skip a number of
+//! hits, remove itself after it stops the machine once, or only stop while a
+//! [`Condition`] over the registers and memory holds, e.g. `A == 0x8000`.
+//!
+//! Watchpoints share the ids of breakpoints and stop the processor once an
+//! instruction reads or writes a watched range of memory, reporting the access as
+//! a [`WatchHit`]. Tick breakpoints stop it before a single clock pulse of an
+//! instruction's microcode, given as a [`MicroTick`].
+
+use crate::{BlueComputer, BlueRegister, MicroTick, RAM_LENGTH, RegisterName};
+use std::{fmt, str::FromStr};
+
+/// A value a condition can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// A processor register, e.g. `A`
+    Register(RegisterName),
+    /// The RAM word at an address, written `[0x20]`
+    Memory(BlueRegister),
+    /// A number
+    Constant(u16),
+}
+
+impl Operand {
+    /// Current value on `computer`
+    fn value(self, computer: &BlueComputer) -> u16 {
+        match self {
+            Self::Register(name) => computer.register(name),
+            Self::Memory(addr) => computer.read_memory(addr).unwrap_or_default(),
+            Self::Constant(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(name) => write!(f, "{name}"),
+            Self::Memory(addr) => write!(f, "[0x{addr:03x}]"),
+            Self::Constant(value) => write!(f, "0x{value:04x}"),
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(addr) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            let addr = crate::debugger::parse_number(addr.trim())?;
+            if usize::from(addr) >= RAM_LENGTH {
+                return Err(format!("address {addr:#x} is outside memory"));
+            }
+            return Ok(Self::Memory(addr));
+        }
+        if let Ok(name) = s.parse() {
+            return Ok(Self::Register(name));
+        }
+        crate::debugger::parse_number(s)
+            .map(Self::Constant)
+            .map_err(|_| format!("'{s}' is not a register, [address] or number"))
+    }
+}
+
+/// How a condition compares its operands, as unsigned 16-bit words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Every comparison, two-character operators first so they are matched whole
+    const ALL: [Self; 6] = [
+        Self::Equal,
+        Self::NotEqual,
+        Self::LessOrEqual,
+        Self::GreaterOrEqual,
+        Self::Less,
+        Self::Greater,
+    ];
+
+    /// Operator as written in a condition
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn holds(self, left: u16, right: u16) -> bool {
+        match self {
+            Self::Equal => left == right,
+            Self::NotEqual => left != right,
+            Self::Less => left < right,
+            Self::LessOrEqual => left <= right,
+            Self::Greater => left > right,
+            Self::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+/// A comparison between two operands, e.g. `[0x20] != 0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    /// Left-hand side
+    pub left: Operand,
+    /// How the sides compare
+    pub comparison: Comparison,
+    /// Right-hand side
+    pub right: Operand,
+}
+
+impl Condition {
+    /// Whether the condition holds on `computer`
+    pub fn holds(&self, computer: &BlueComputer) -> bool {
+        self.comparison
+            .holds(self.left.value(computer), self.right.value(computer))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.left,
+            self.comparison.symbol(),
+            self.right
+        )
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (at, comparison) = s
+            .char_indices()
+            .find_map(|(i, _)| {
+                Comparison::ALL
+                    .into_iter()
+                    .find(|c| s[i..].starts_with(c.symbol()))
+                    .map(|c| (i, c))
+            })
+            .ok_or_else(|| format!("'{s}' has no comparison (==, !=, <, <=, >, >=)"))?;
+        Ok(Self {
+            left: s[..at].parse()?,
+            comparison,
+            right: s[at + comparison.symbol().len()..].parse()?,
+        })
+    }
+}
+
+/// A place to stop, and what happens when execution reaches it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    /// Number the console refers to the breakpoint by
+    pub id: u32,
+    /// Address of the instruction to stop before
+    pub address: BlueRegister,
+    /// Whether the breakpoint is checked at all
+    pub enabled: bool,
+    /// Only stop while this holds
+    pub condition: Option<Condition>,
+    /// Hits still to pass without stopping
+    pub ignore: u32,
+    /// Times execution reached the address with the condition holding
+    pub hits: u32,
+    /// Whether the breakpoint is removed once it stops the machine
+    pub temporary: bool,
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<3} line {:<5} {:<8} hits {}",
+            self.id,
+            self.address,
+            if self.enabled { "enabled" } else { "disabled" },
+            self.hits
+        )?;
+        if self.ignore > 0 {
+            write!(f, ", ignoring {}", self.ignore)?;
+        }
+        if self.temporary {
+            write!(f, ", temporary")?;
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " if {condition}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Which memory accesses a watchpoint catches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    /// Instruction fetches and operand reads
+    Read,
+    /// STA
+    Write,
+    /// Both reads and writes
+    Access,
+}
+
+impl WatchKind {
+    /// Whether a read, or a write if `write` is set, is caught
+    const fn catches(self, write: bool) -> bool {
+        match self {
+            Self::Read => !write,
+            Self::Write => write,
+            Self::Access => true,
+        }
+    }
+}
+
+impl fmt::Display for WatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read => write!(f, "read"),
+            Self::Write => write!(f, "write"),
+            Self::Access => write!(f, "access"),
+        }
+    }
+}
+
+/// A range of memory to watch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    /// Number the console refers to the watchpoint by, shared with breakpoints
+    pub id: u32,
+    /// First address watched
+    pub start: BlueRegister,
+    /// Last address watched
+    pub end: BlueRegister,
+    /// Accesses that stop the machine
+    pub kind: WatchKind,
+    /// Whether the watchpoint is checked at all
+    pub enabled: bool,
+    /// Times an instruction made a watched access
+    pub hits: u32,
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = if self.start == self.end {
+            format!("line {}", self.start)
+        } else {
+            format!("lines {}-{}", self.start, self.end)
+        };
+        write!(
+            f,
+            "{:<3} {lines} {:<8} hits {}, watching {}",
+            self.id,
+            if self.enabled { "enabled" } else { "disabled" },
+            self.hits,
+            self.kind
+        )
+    }
+}
+
+/// A memory access that triggered a watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// Id of the watchpoint
+    pub id: u32,
+    /// Address of the instruction that made the access
+    pub instruction: BlueRegister,
+    /// Address accessed
+    pub address: BlueRegister,
+    /// Whether the access was a write
+    pub write: bool,
+    /// Value before the access
+    pub old: u16,
+    /// Value after the access, the same as `old` for reads
+    pub new: u16,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.write {
+            write!(
+                f,
+                "Watchpoint {}: line {} wrote line {}: {:04x} -> {:04x}",
+                self.id, self.instruction, self.address, self.old, self.new
+            )
+        } else {
+            write!(
+                f,
+                "Watchpoint {}: line {} read line {}: {:04x}",
+                self.id, self.instruction, self.address, self.old
+            )
+        }
+    }
+}
+
+/// A breakpoint on one clock pulse of an instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickBreakpoint {
+    /// Number the console refers to the breakpoint by, shared with breakpoints
+    pub id: u32,
+    /// The pulse the machine stops before
+    pub tick: MicroTick,
+    /// Whether the breakpoint is checked at all
+    pub enabled: bool,
+    /// Times the machine reached the pulse while enabled
+    pub hits: u32,
+}
+
+impl fmt::Display for TickBreakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<3} {} {:<8} hits {}",
+            self.id,
+            self.tick,
+            if self.enabled { "enabled" } else { "disabled" },
+            self.hits
+        )
+    }
+}
+
+/// The breakpoints and watchpoints set on a machine, in the order they were added
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoints {
+    list: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    ticks: Vec<TickBreakpoint>,
+    next_id: u32,
+}
+
+impl Breakpoints {
+    /// Add an enabled, unconditional breakpoint, returning its id
+    pub fn add(&mut self, address: BlueRegister) -> u32 {
+        self.next_id += 1;
+        self.list.push(Breakpoint {
+            id: self.next_id,
+            address,
+            enabled: true,
+            condition: None,
+            ignore: 0,
+            hits: 0,
+            temporary: false,
+        });
+        self.next_id
+    }
+
+    /// Add a breakpoint that is removed once it stops the machine
+    pub fn add_temporary(&mut self, address: BlueRegister) -> u32 {
+        let id = self.add(address);
+        if let Some(breakpoint) = self.get_mut(id) {
+            breakpoint.temporary = true;
+        }
+        id
+    }
+
+    /// The breakpoint with `id`
+    pub fn get(&self, id: u32) -> Option<&Breakpoint> {
+        self.list.iter().find(|b| b.id == id)
+    }
+
+    /// Mutable access to the breakpoint with `id`, to change its settings
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Breakpoint> {
+        self.list.iter_mut().find(|b| b.id == id)
+    }
+
+    /// Delete the breakpoint with `id`
+    pub fn remove(&mut self, id: u32) -> Option<Breakpoint> {
+        let index = self.list.iter().position(|b| b.id == id)?;
+        Some(self.list.remove(index))
+    }
+
+    /// Delete every breakpoint, keeping the watchpoints; ids are not reused
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    /// Every breakpoint, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.list.iter()
+    }
+
+    /// Number of breakpoints
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Whether there are no breakpoints
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Add an enabled watchpoint over `start..=end`, returning its id
+    pub fn add_watchpoint(
+        &mut self,
+        start: BlueRegister,
+        end: BlueRegister,
+        kind: WatchKind,
+    ) -> u32 {
+        self.next_id += 1;
+        self.watchpoints.push(Watchpoint {
+            id: self.next_id,
+            start: start.min(end),
+            end: start.max(end),
+            kind,
+            enabled: true,
+            hits: 0,
+        });
+        self.next_id
+    }
+
+    /// The watchpoint with `id`
+    pub fn watchpoint(&self, id: u32) -> Option<&Watchpoint> {
+        self.watchpoints.iter().find(|w| w.id == id)
+    }
+
+    /// Mutable access to the watchpoint with `id`, to change its settings
+    pub fn watchpoint_mut(&mut self, id: u32) -> Option<&mut Watchpoint> {
+        self.watchpoints.iter_mut().find(|w| w.id == id)
+    }
+
+    /// Delete the watchpoint with `id`
+    pub fn remove_watchpoint(&mut self, id: u32) -> Option<Watchpoint> {
+        let index = self.watchpoints.iter().position(|w| w.id == id)?;
+        Some(self.watchpoints.remove(index))
+    }
+
+    /// Every watchpoint, in the order they were added
+    pub fn watchpoints(&self) -> impl Iterator<Item = &Watchpoint> {
+        self.watchpoints.iter()
+    }
+
+    /// Count a hit on the watchpoints catching an access to `address`, returning
+    /// the id of the first
+    pub(crate) fn watch_hit(&mut self, address: BlueRegister, write: bool) -> Option<u32> {
+        let mut first = None;
+        for watchpoint in &mut self.watchpoints {
+            if watchpoint.enabled
+                && (watchpoint.start..=watchpoint.end).contains(&address)
+                && watchpoint.kind.catches(write)
+            {
+                watchpoint.hits += 1;
+                first = first.or(Some(watchpoint.id));
+            }
+        }
+        first
+    }
+
+    /// Add an enabled breakpoint before `tick`, returning its id
+    pub fn add_tick_breakpoint(&mut self, tick: MicroTick) -> u32 {
+        self.next_id += 1;
+        self.ticks.push(TickBreakpoint {
+            id: self.next_id,
+            tick,
+            enabled: true,
+            hits: 0,
+        });
+        self.next_id
+    }
+
+    /// The tick breakpoint with `id`
+    pub fn tick_breakpoint(&self, id: u32) -> Option<&TickBreakpoint> {
+        self.ticks.iter().find(|t| t.id == id)
+    }
+
+    /// Mutable access to the tick breakpoint with `id`, to change its settings
+    pub fn tick_breakpoint_mut(&mut self, id: u32) -> Option<&mut TickBreakpoint> {
+        self.ticks.iter_mut().find(|t| t.id == id)
+    }
+
+    /// Delete the tick breakpoint with `id`
+    pub fn remove_tick_breakpoint(&mut self, id: u32) -> Option<TickBreakpoint> {
+        let index = self.ticks.iter().position(|t| t.id == id)?;
+        Some(self.ticks.remove(index))
+    }
+
+    /// Every tick breakpoint, in the order they were added
+    pub fn tick_breakpoints(&self) -> impl Iterator<Item = &TickBreakpoint> {
+        self.ticks.iter()
+    }
+
+    /// Whether any tick breakpoint is enabled, so execution must go tick by tick
+    pub(crate) fn has_tick_breakpoints(&self) -> bool {
+        self.ticks.iter().any(|t| t.enabled)
+    }
+
+    /// Count a hit on the enabled tick breakpoints before `tick`, returning the id
+    /// of the first
+    pub(crate) fn tick_hit(&mut self, tick: MicroTick) -> Option<u32> {
+        let mut first = None;
+        for breakpoint in &mut self.ticks {
+            if breakpoint.enabled && breakpoint.tick == tick {
+                breakpoint.hits += 1;
+                first = first.or(Some(breakpoint.id));
+            }
+        }
+        first
+    }
+
+    /// Whether an enabled breakpoint at `address` has its condition holding on
+    /// `computer`, without counting a hit
+    pub fn matches(&self, address: BlueRegister, computer: &BlueComputer) -> bool {
+        self.list.iter().any(|b| {
+            b.enabled
+                && b.address == address
+                && b.condition
+                    .is_none_or(|condition| condition.holds(computer))
+        })
+    }
+
+    /// Count a hit on every breakpoint matching at `address` and decide whether
+    /// to stop
+    ///
+    /// A breakpoint with hits to ignore uses one up instead of stopping; a
+    /// temporary breakpoint that stops the machine is removed.
+    pub(crate) fn hit(&mut self, address: BlueRegister, computer: &BlueComputer) -> bool {
+        let matching: Vec<u32> = self
+            .list
+            .iter()
+            .filter(|b| {
+                b.enabled
+                    && b.address == address
+                    && b.condition
+                        .is_none_or(|condition| condition.holds(computer))
+            })
+            .map(|b| b.id)
+            .collect();
+        let mut stop = false;
+        for id in matching {
+            let Some(breakpoint) = self.get_mut(id) else {
+                continue;
+            };
+            breakpoint.hits += 1;
+            if breakpoint.ignore > 0 {
+                breakpoint.ignore -= 1;
+                continue;
+            }
+            stop = true;
+            if breakpoint.temporary {
+                self.remove(id);
+            }
+        }
+        stop
+    }
+}
@@ -0,0 +1,1018 @@
+//! # Debugger
+//!
+//! The command language of the interactive console. Each line is parsed into a
+//! [`Command`] and carried out by a [`Debugger`] against a [`BlueComputer`].
+//!
+//! Commands have a long name and short aliases (`break` or `b`), and the one-letter
+//! forms may be written without a space (`b100`, `xA 42`). Numbers are decimal,
+//! `0x` hex or `0b` binary. Lines can come from the keyboard or be queued from a
+//! script; `#` starts a comment.
+
+use crate::{
+    BlueComputer, BlueRegister, MicroTick, PauseReason, RAM_LENGTH, RegisterName, RunState,
+    StopReason, asm,
+    breakpoint::{Breakpoint, Condition, WatchKind},
+    disasm,
+    journal::Granularity,
+    microcode::{self, TICKS},
+    snapshot::Snapshot,
+};
+use std::{
+    collections::VecDeque,
+    fmt, fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// A memory word or a register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// The RAM word at an address
+    Memory(BlueRegister),
+    /// A processor register
+    Register(RegisterName),
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    /// A register name, or else an address
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(register) => Ok(Self::Register(register)),
+            Err(_) if parse_number(s).is_err() => {
+                Err(format!("'{s}' is neither a register nor an address"))
+            }
+            Err(_) => parse_address(s).map(Self::Memory),
+        }
+    }
+}
+
+/// One debugger command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Resume a paused or halted machine
+    Continue,
+    /// Run one instruction, then pause
+    Step,
+    /// Print the registers
+    Registers,
+    /// Print all of RAM
+    Dump,
+    /// Print `count` words starting at a location
+    Examine {
+        /// Where to start
+        location: Location,
+        /// Number of words; registers ignore it
+        count: u16,
+    },
+    /// Store values at consecutive addresses, or one value in a register
+    Deposit {
+        /// Where to store
+        location: Location,
+        /// Values to store
+        values: Vec<u16>,
+    },
+    /// Set a breakpoint
+    Break {
+        /// Address of the instruction to stop before
+        address: BlueRegister,
+        /// Only stop while this holds
+        condition: Option<Condition>,
+        /// Remove the breakpoint once it stops the machine
+        temporary: bool,
+    },
+    /// Run clock pulses one at a time, showing the registers each one changes
+    Tick(u16),
+    /// Stop before a clock pulse of an instruction
+    TickBreak(MicroTick),
+    /// Turn explain mode on or off, or with `None` show whether it is on
+    Explain(Option<bool>),
+    /// Watch a range of memory
+    Watch {
+        /// First address watched
+        start: BlueRegister,
+        /// Last address watched
+        end: BlueRegister,
+        /// Accesses that stop the machine
+        kind: WatchKind,
+    },
+    /// List the breakpoints and watchpoints
+    Breakpoints,
+    /// Delete a breakpoint or watchpoint by id
+    Delete(u32),
+    /// Check a breakpoint or watchpoint again
+    Enable(u32),
+    /// Stop checking a breakpoint or watchpoint without deleting it
+    Disable(u32),
+    /// Pass the next hits of a breakpoint without stopping
+    Ignore {
+        /// Breakpoint id
+        id: u32,
+        /// Hits to pass
+        count: u32,
+    },
+    /// Set or, with `None`, remove the condition of a breakpoint
+    Condition {
+        /// Breakpoint id
+        id: u32,
+        /// The new condition
+        condition: Option<Condition>,
+    },
+    /// Disassemble 8 words from an address, or from the PC
+    List(Option<BlueRegister>),
+    /// Save a snapshot of the machine
+    Save(PathBuf),
+    /// Restore a snapshot
+    Load(PathBuf),
+    /// Undo the last instruction
+    ReverseStep,
+    /// Run backwards to the previous breakpoint
+    ReverseContinue,
+    /// Show which instruction last wrote an address
+    Who(BlueRegister),
+    /// Change how much history is kept
+    History {
+        /// Number of steps kept
+        depth: usize,
+        /// Whether steps are instructions or ticks
+        granularity: Granularity,
+    },
+    /// Press the ON switch
+    On,
+    /// Press the OFF switch
+    Off,
+    /// Show the run state
+    Status,
+    /// Run the commands in a file
+    Source(PathBuf),
+    /// List the commands, or describe one
+    Help(Option<String>),
+    /// Leave the console
+    Quit,
+}
+
+/// Names and description of a command, for parsing and `help`
+struct Spec {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    usage: &'static str,
+    description: &'static str,
+}
+
+const COMMANDS: [Spec; 33] = [
+    Spec {
+        name: "continue",
+        aliases: &["c"],
+        usage: "continue",
+        description: "Resume a paused or halted machine",
+    },
+    Spec {
+        name: "step",
+        aliases: &["s"],
+        usage: "step",
+        description: "Run one instruction, then pause",
+    },
+    Spec {
+        name: "tick",
+        aliases: &["t"],
+        usage: "tick [<count>]",
+        description: "Run one clock pulse (or count), showing the registers it changes",
+    },
+    Spec {
+        name: "explain",
+        aliases: &[],
+        usage: "explain [on|off]",
+        description: "Narrate every micro-operation as it runs",
+    },
+    Spec {
+        name: "regs",
+        aliases: &["r"],
+        usage: "regs",
+        description: "Print the registers",
+    },
+    Spec {
+        name: "dump",
+        aliases: &["d"],
+        usage: "dump",
+        description: "Print all of RAM",
+    },
+    Spec {
+        name: "examine",
+        aliases: &["e"],
+        usage: "examine <addr|reg> [<count>]",
+        description: "Print memory words or a register",
+    },
+    Spec {
+        name: "deposit",
+        aliases: &["x", "dep"],
+        usage: "deposit <addr|reg> <value>...",
+        description: "Store words from an address, or a value in a register",
+    },
+    Spec {
+        name: "break",
+        aliases: &["b"],
+        usage: "break [<addr> [if <condition>]]",
+        description: "Set a breakpoint, or list them",
+    },
+    Spec {
+        name: "tbreak",
+        aliases: &["tb"],
+        usage: "tbreak <addr> [if <condition>]",
+        description: "Set a breakpoint that is deleted once it stops the machine",
+    },
+    Spec {
+        name: "mbreak",
+        aliases: &["mb"],
+        usage: "mbreak <opcode> fetch|execute <tick>",
+        description: "Stop before a clock pulse of an instruction (e.g., mbreak LDA execute 3)",
+    },
+    Spec {
+        name: "watch",
+        aliases: &["w"],
+        usage: "watch <addr> [<end>]",
+        description: "Stop after an instruction writes an address or range",
+    },
+    Spec {
+        name: "rwatch",
+        aliases: &["rw"],
+        usage: "rwatch <addr> [<end>]",
+        description: "Stop after an instruction reads an address or range",
+    },
+    Spec {
+        name: "awatch",
+        aliases: &["aw"],
+        usage: "awatch <addr> [<end>]",
+        description: "Stop after an instruction reads or writes an address or range",
+    },
+    Spec {
+        name: "breakpoints",
+        aliases: &["bl"],
+        usage: "breakpoints",
+        description: "List the breakpoints and watchpoints with their ids and hit counts",
+    },
+    Spec {
+        name: "delete",
+        aliases: &["del"],
+        usage: "delete <id>",
+        description: "Delete a breakpoint or watchpoint",
+    },
+    Spec {
+        name: "enable",
+        aliases: &[],
+        usage: "enable <id>",
+        description: "Enable a breakpoint or watchpoint",
+    },
+    Spec {
+        name: "disable",
+        aliases: &[],
+        usage: "disable <id>",
+        description: "Disable a breakpoint or watchpoint without deleting it",
+    },
+    Spec {
+        name: "ignore",
+        aliases: &[],
+        usage: "ignore <id> <count>",
+        description: "Pass the next hits of a breakpoint without stopping",
+    },
+    Spec {
+        name: "condition",
+        aliases: &["cond"],
+        usage: "condition <id> [<condition>]",
+        description: "Set or remove the condition of a breakpoint (e.g., A == 0x8000)",
+    },
+    Spec {
+        name: "list",
+        aliases: &["l"],
+        usage: "list [<addr>]",
+        description: "Disassemble 8 words from the PC or an address",
+    },
+    Spec {
+        name: "save",
+        aliases: &[],
+        usage: "save <file>",
+        description: "Save a snapshot (text if the file ends in .txt)",
+    },
+    Spec {
+        name: "load",
+        aliases: &[],
+        usage: "load <file>",
+        description: "Restore a snapshot, stopped as it was saved",
+    },
+    Spec {
+        name: "rstep",
+        aliases: &["rs"],
+        usage: "rstep",
+        description: "Reverse step: undo the last instruction",
+    },
+    Spec {
+        name: "rcontinue",
+        aliases: &["rc"],
+        usage: "rcontinue",
+        description: "Reverse continue: run backwards to the previous breakpoint",
+    },
+    Spec {
+        name: "who",
+        aliases: &[],
+        usage: "who <addr>",
+        description: "Show which instruction last wrote an address",
+    },
+    Spec {
+        name: "history",
+        aliases: &[],
+        usage: "history <depth> [tick]",
+        description: "Keep the last instructions (or ticks) of history",
+    },
+    Spec {
+        name: "on",
+        aliases: &[],
+        usage: "on",
+        description: "Press ON: start the clock, clearing a halt or fault",
+    },
+    Spec {
+        name: "off",
+        aliases: &[],
+        usage: "off",
+        description: "Press OFF",
+    },
+    Spec {
+        name: "status",
+        aliases: &["st"],
+        usage: "status",
+        description: "Show the run state and why the machine stopped",
+    },
+    Spec {
+        name: "source",
+        aliases: &[],
+        usage: "source <file>",
+        description: "Run the commands in a file",
+    },
+    Spec {
+        name: "help",
+        aliases: &["h", "?"],
+        usage: "help [<command>]",
+        description: "List the commands, or describe one",
+    },
+    Spec {
+        name: "quit",
+        aliases: &["q", "exit"],
+        usage: "quit",
+        description: "Leave the emulator",
+    },
+];
+
+/// Find a command by its name or an alias
+fn lookup(word: &str) -> Option<&'static Spec> {
+    let word = word.to_ascii_lowercase();
+    COMMANDS
+        .iter()
+        .find(|spec| spec.name == word || spec.aliases.contains(&word.as_str()))
+}
+
+/// Parse a decimal, `0x` hex or `0b` binary word
+pub fn parse_number(text: &str) -> Result<u16, String> {
+    asm::parse_number(text)
+        .and_then(|n| u16::try_from(n).ok())
+        .ok_or_else(|| format!("invalid number '{text}'"))
+}
+
+/// Parse a breakpoint id
+fn parse_id(text: &str) -> Result<u32, String> {
+    parse_number(text)
+        .map(u32::from)
+        .map_err(|_| format!("invalid breakpoint id '{text}'"))
+}
+
+/// Parse a number that must be a RAM address
+fn parse_address(text: &str) -> Result<BlueRegister, String> {
+    let addr = parse_number(text)?;
+    if usize::from(addr) < RAM_LENGTH {
+        Ok(addr)
+    } else {
+        Err(format!(
+            "address '{text}' is outside memory (0 to {})",
+            RAM_LENGTH - 1
+        ))
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let first = words.next().ok_or("empty command")?;
+        let mut args: Vec<&str> = Vec::new();
+        let spec = match lookup(first) {
+            Some(spec) => spec,
+            // One-letter commands may be run together with their first argument
+            None => {
+                let (letter, rest) = first.split_at(first.chars().next().map_or(0, char::len_utf8));
+                match lookup(letter) {
+                    Some(spec) if matches!(spec.name, "break" | "list" | "deposit") => {
+                        args.push(rest);
+                        spec
+                    }
+                    _ => {
+                        return Err(format!("unknown command '{first}', type 'help' for a list"));
+                    }
+                }
+            }
+        };
+        args.extend(words);
+
+        let usage = || format!("usage: {}", spec.usage);
+        let path = |args: &[&str]| match args {
+            [] => Err(usage()),
+            _ => Ok(PathBuf::from(args.join(" "))),
+        };
+        let command = match (spec.name, args.as_slice()) {
+            ("continue", []) => Self::Continue,
+            ("step", []) => Self::Step,
+            ("tick", []) => Self::Tick(1),
+            ("tick", [count]) => Self::Tick(parse_number(count)?),
+            ("explain", []) => Self::Explain(None),
+            ("explain", [switch]) => match switch.to_ascii_lowercase().as_str() {
+                "on" => Self::Explain(Some(true)),
+                "off" => Self::Explain(Some(false)),
+                _ => return Err(usage()),
+            },
+            ("regs", []) => Self::Registers,
+            ("dump", []) => Self::Dump,
+            ("examine", [location]) => Self::Examine {
+                location: location.parse()?,
+                count: 1,
+            },
+            ("examine", [location, count]) => Self::Examine {
+                location: location.parse()?,
+                count: parse_number(count)?,
+            },
+            ("deposit", [location, values @ ..]) if !values.is_empty() => {
+                let location: Location = location.parse()?;
+                let values = values
+                    .iter()
+                    .map(|value| parse_number(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if matches!(location, Location::Register(_)) && values.len() > 1 {
+                    return Err("a register takes a single value".to_string());
+                }
+                Self::Deposit { location, values }
+            }
+            ("break" | "breakpoints", []) => Self::Breakpoints,
+            ("break" | "tbreak", [addr, rest @ ..]) => Self::Break {
+                address: parse_address(addr)?,
+                condition: match rest {
+                    [] => None,
+                    ["if", condition @ ..] if !condition.is_empty() => {
+                        Some(condition.join(" ").parse()?)
+                    }
+                    _ => return Err(usage()),
+                },
+                temporary: spec.name == "tbreak",
+            },
+            ("mbreak", [opcode, state, tick]) => Self::TickBreak(MicroTick {
+                opcode: microcode::parse_opcode(opcode)?
+                    .ok_or("a tick breakpoint needs an opcode")?,
+                state: state.parse()?,
+                tick: parse_number(tick)
+                    .ok()
+                    .and_then(|tick| u8::try_from(tick).ok())
+                    .filter(|&tick| usize::from(tick) < TICKS)
+                    .ok_or_else(|| format!("tick '{tick}' is not 0-7"))?,
+            }),
+            ("watch" | "rwatch" | "awatch", [start, end @ ..]) if end.len() <= 1 => {
+                let start = parse_address(start)?;
+                Self::Watch {
+                    start,
+                    end: end.first().map_or(Ok(start), |end| parse_address(end))?,
+                    kind: match spec.name {
+                        "watch" => WatchKind::Write,
+                        "rwatch" => WatchKind::Read,
+                        _ => WatchKind::Access,
+                    },
+                }
+            }
+            ("delete", [id]) => Self::Delete(parse_id(id)?),
+            ("enable", [id]) => Self::Enable(parse_id(id)?),
+            ("disable", [id]) => Self::Disable(parse_id(id)?),
+            ("ignore", [id, count]) => Self::Ignore {
+                id: parse_id(id)?,
+                count: parse_number(count)?.into(),
+            },
+            ("condition", [id, condition @ ..]) => Self::Condition {
+                id: parse_id(id)?,
+                condition: match condition {
+                    [] => None,
+                    _ => Some(condition.join(" ").parse()?),
+                },
+            },
+            ("list", []) => Self::List(None),
+            ("list", [addr]) => Self::List(Some(parse_address(addr)?)),
+            ("save", args) => Self::Save(path(args)?),
+            ("load", args) => Self::Load(path(args)?),
+            ("source", args) => Self::Source(path(args)?),
+            ("rstep", []) => Self::ReverseStep,
+            ("rcontinue", []) => Self::ReverseContinue,
+            ("who", [addr]) => Self::Who(parse_address(addr)?),
+            ("history", [depth]) | ("history", [depth, "instruction"]) => Self::History {
+                depth: parse_number(depth)?.into(),
+                granularity: Granularity::Instruction,
+            },
+            ("history", [depth, "tick"]) => Self::History {
+                depth: parse_number(depth)?.into(),
+                granularity: Granularity::Tick,
+            },
+            ("on", []) => Self::On,
+            ("off", []) => Self::Off,
+            ("status", []) => Self::Status,
+            ("help", []) => Self::Help(None),
+            ("help", [topic]) => Self::Help(Some((*topic).to_string())),
+            ("quit", []) => Self::Quit,
+            _ => return Err(usage()),
+        };
+        Ok(command)
+    }
+}
+
+/// What the console does after a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Wait for the next command
+    Prompt,
+    /// Let the machine run
+    Run,
+    /// Run one instruction, then pause
+    Step,
+    /// Leave the console
+    Quit,
+}
+
+/// Command source and interpreter for the interactive console
+#[derive(Default)]
+pub struct Debugger {
+    /// Script lines still to run, before reading the keyboard again
+    queued: VecDeque<String>,
+    /// Whether the last line came from a script
+    scripted: bool,
+    /// Where lines come from once the queue is empty, standard input if `None`
+    source: Option<Box<dyn BufRead>>,
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("queued", &self.queued)
+            .field("scripted", &self.scripted)
+            .field("source", &self.source.as_ref().map(|_| "reader"))
+            .finish()
+    }
+}
+
+impl Debugger {
+    /// A debugger reading commands from standard input
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the lines of `text` before reading any more from the keyboard
+    ///
+    /// Queued commands run ahead of anything queued earlier, so a script can
+    /// `source` another.
+    pub fn queue_commands(&mut self, text: &str) {
+        for line in text.lines().rev() {
+            self.queued.push_front(line.to_string());
+        }
+    }
+
+    /// Queue the commands in a script file
+    pub fn queue_script(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.queue_commands(&fs::read_to_string(path)?);
+        Ok(())
+    }
+
+    /// Read commands from `source` instead of standard input
+    ///
+    /// For when standard input is taken by something else, such as the INP script
+    /// of `--input -`.
+    pub fn set_command_source(&mut self, source: impl BufRead + 'static) {
+        self.source = Some(Box::new(source));
+    }
+
+    /// Number of script lines still queued
+    pub fn queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// The next command line, from a script, the command source or standard input
+    ///
+    /// Returns `None` at the end of the input.
+    pub fn next_line(&mut self) -> io::Result<Option<String>> {
+        if let Some(line) = self.queued.pop_front() {
+            self.scripted = true;
+            if !line.trim().is_empty() {
+                println!("> {}", line.trim());
+            }
+            return Ok(Some(line));
+        }
+        self.scripted = false;
+        let mut line = String::new();
+        let read = match &mut self.source {
+            Some(source) => source.read_line(&mut line)?,
+            None => io::stdin().lock().read_line(&mut line)?,
+        };
+        match read {
+            0 => Ok(None),
+            _ => Ok(Some(line)),
+        }
+    }
+
+    /// Parse and run one line; blank lines and comments do nothing
+    ///
+    /// An error in a script line abandons the rest of the script.
+    pub fn run_line(&mut self, computer: &mut BlueComputer, line: &str) -> Flow {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            return Flow::Prompt;
+        }
+        match line.parse() {
+            Ok(command) => self.execute(computer, command),
+            Err(e) => {
+                println!("Error: {e}");
+                if self.scripted && !self.queued.is_empty() {
+                    println!("Skipping the rest of the script");
+                    self.queued.clear();
+                }
+                Flow::Prompt
+            }
+        }
+    }
+
+    /// Carry out a command
+    pub fn execute(&mut self, computer: &mut BlueComputer, command: Command) -> Flow {
+        match command {
+            Command::Continue => return start(computer, Flow::Run),
+            Command::Step => return start(computer, Flow::Step),
+            Command::Tick(count) => {
+                if start(computer, Flow::Step) == Flow::Step {
+                    tick(computer, count);
+                }
+            }
+            Command::Explain(switch) => {
+                if let Some(enabled) = switch {
+                    computer.set_explain_mode(enabled);
+                }
+                let mode = if computer.explain_mode() { "on" } else { "off" };
+                println!("Explain mode is {mode}");
+            }
+            Command::TickBreak(at) => {
+                let id = computer.breakpoints_mut().add_tick_breakpoint(at);
+                println!("Set tick breakpoint {id} before {at}");
+            }
+            Command::Registers => print_registers(computer),
+            Command::Dump => print_memory(computer),
+            Command::Examine { location, count } => examine(computer, location, count),
+            Command::Deposit { location, values } => deposit(computer, location, &values),
+            Command::Break {
+                address,
+                condition,
+                temporary,
+            } => {
+                let breakpoints = computer.breakpoints_mut();
+                let id = if temporary {
+                    breakpoints.add_temporary(address)
+                } else {
+                    breakpoints.add(address)
+                };
+                if let Some(breakpoint) = breakpoints.get_mut(id) {
+                    breakpoint.condition = condition;
+                }
+                println!("Set breakpoint {id} at line {address}");
+            }
+            Command::Watch { start, end, kind } => {
+                let id = computer.breakpoints_mut().add_watchpoint(start, end, kind);
+                if let Some(watchpoint) = computer.breakpoints().watchpoint(id) {
+                    println!("Set watchpoint {watchpoint}");
+                }
+            }
+            Command::Breakpoints => {
+                let breakpoints = computer.breakpoints();
+                if breakpoints.is_empty()
+                    && breakpoints.watchpoints().next().is_none()
+                    && breakpoints.tick_breakpoints().next().is_none()
+                {
+                    println!("No breakpoints");
+                }
+                for breakpoint in breakpoints.iter() {
+                    println!("{breakpoint}");
+                }
+                for watchpoint in breakpoints.watchpoints() {
+                    println!("{watchpoint}");
+                }
+                for breakpoint in breakpoints.tick_breakpoints() {
+                    println!("{breakpoint}");
+                }
+            }
+            Command::Delete(id) => {
+                let breakpoints = computer.breakpoints_mut();
+                if breakpoints.remove(id).is_some()
+                    || breakpoints.remove_watchpoint(id).is_some()
+                    || breakpoints.remove_tick_breakpoint(id).is_some()
+                {
+                    println!("Deleted {id}");
+                } else {
+                    println!("Error: no breakpoint {id}");
+                }
+            }
+            Command::Enable(id) | Command::Disable(id) => {
+                let enabled = matches!(command, Command::Enable(_));
+                let breakpoints = computer.breakpoints_mut();
+                if let Some(watchpoint) = breakpoints.watchpoint_mut(id) {
+                    watchpoint.enabled = enabled;
+                    println!("{watchpoint}");
+                } else if let Some(breakpoint) = breakpoints.tick_breakpoint_mut(id) {
+                    breakpoint.enabled = enabled;
+                    println!("{breakpoint}");
+                } else {
+                    change_breakpoint(computer, id, |b| b.enabled = enabled);
+                }
+            }
+            Command::Ignore { id, count } => {
+                change_breakpoint(computer, id, |b| b.ignore = count);
+            }
+            Command::Condition { id, condition } => {
+                change_breakpoint(computer, id, |b| b.condition = condition);
+            }
+            Command::List(addr) => print_listing(computer, addr.unwrap_or(computer.pc())),
+            Command::Save(path) => match computer.save_state().write_file(&path) {
+                Ok(()) => println!("Saved state to {}", path.display()),
+                Err(e) => println!("Failed to save state: {e}"),
+            },
+            Command::Load(path) => match Snapshot::read_file(&path) {
+                Ok(snapshot) => {
+                    computer.load_state(&snapshot);
+                    println!("Loaded state from {}", path.display());
+                }
+                Err(e) => println!("Failed to load state: {e}"),
+            },
+            Command::ReverseStep => {
+                if computer.reverse_step() {
+                    print_registers(computer);
+                } else {
+                    println!("No history to step back through");
+                }
+            }
+            Command::ReverseContinue => {
+                match computer.reverse_continue() {
+                    Some(line) => println!("Stopped at line {line}"),
+                    None => println!("Reached the start of the history"),
+                }
+                print_registers(computer);
+            }
+            Command::Who(addr) => match computer.history().last_write(addr) {
+                Some(write) => println!(
+                    "Line {addr} last written by line {}: {:04x} -> {:04x}",
+                    write.instruction, write.old, write.new
+                ),
+                None => println!("No write to line {addr} in the history"),
+            },
+            Command::History { depth, granularity } => {
+                computer.set_history(depth, granularity);
+                println!("Keeping {depth} steps of history");
+            }
+            Command::On => {
+                computer.press_on();
+                return Flow::Run;
+            }
+            Command::Off => computer.press_off(),
+            Command::Status => println!("State: {}", computer.run_state()),
+            Command::Source(path) => {
+                if let Err(e) = self.queue_script(&path) {
+                    println!("Failed to read {}: {e}", path.display());
+                }
+            }
+            Command::Help(topic) => help(topic.as_deref()),
+            Command::Quit => {
+                println!("Stopping...");
+                return Flow::Quit;
+            }
+        }
+        Flow::Prompt
+    }
+}
+
+/// Start the clock for `flow`, explaining why it cannot be started
+fn start(computer: &mut BlueComputer, flow: Flow) -> Flow {
+    if computer.resume() {
+        return flow;
+    }
+    match computer.run_state() {
+        RunState::Faulted(fault) => println!("Faulted: {fault}. Press ON (on) to restart"),
+        _ => println!("Powered off. Press ON (on) first"),
+    }
+    Flow::Prompt
+}
+
+/// Run up to `count` clock pulses, printing each one and the registers it changed,
+/// then pause
+fn tick(computer: &mut BlueComputer, count: u16) {
+    const SHOWN: [RegisterName; 6] = [
+        RegisterName::Pc,
+        RegisterName::Mar,
+        RegisterName::Mbr,
+        RegisterName::Ir,
+        RegisterName::Z,
+        RegisterName::A,
+    ];
+    for _ in 0..count.max(1) {
+        let at = computer.next_tick().map_or_else(
+            || format!("{} tick {}", computer.state(), computer.clock_pulse()),
+            |at| at.to_string(),
+        );
+        let before = SHOWN.map(|name| computer.register(name));
+        let stop = computer.step_tick();
+        let changes: Vec<String> = SHOWN
+            .iter()
+            .zip(before)
+            .filter(|&(&name, old)| computer.register(name) != old)
+            .map(|(&name, old)| format!("{name} {old:04x} -> {:04x}", computer.register(name)))
+            .collect();
+        if changes.is_empty() {
+            println!("{at}: no transfers");
+        } else {
+            println!("{at}: {}", changes.join(", "));
+        }
+        for event in computer.take_micro_events() {
+            println!("  {event}");
+        }
+        match stop {
+            None => (),
+            Some(StopReason::TickBreakpoint(next)) => {
+                println!("Stopped before {next}");
+                break;
+            }
+            Some(StopReason::Watchpoint(hit)) => {
+                println!("{hit}");
+                break;
+            }
+            Some(StopReason::WaitingForIo) => {
+                println!("Waiting for I/O");
+                break;
+            }
+            Some(_) => {
+                println!("State: {}", computer.run_state());
+                break;
+            }
+        }
+    }
+    computer.pause(PauseReason::Step);
+}
+
+/// Apply `change` to a breakpoint and show the result
+fn change_breakpoint(computer: &mut BlueComputer, id: u32, change: impl FnOnce(&mut Breakpoint)) {
+    match computer.breakpoints_mut().get_mut(id) {
+        Some(breakpoint) => {
+            change(breakpoint);
+            println!("{breakpoint}");
+        }
+        None => println!("Error: no breakpoint {id}"),
+    }
+}
+
+/// Print the command list, or the usage of one command
+fn help(topic: Option<&str>) {
+    match topic {
+        None => {
+            println!("Commands (numbers are decimal, 0x hex or 0b binary):");
+            for spec in &COMMANDS {
+                let names = [spec.name]
+                    .iter()
+                    .chain(spec.aliases)
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("  {names:<22}{}", spec.description);
+            }
+        }
+        Some(topic) => match lookup(topic) {
+            Some(spec) => {
+                println!("Usage: {}", spec.usage);
+                if !spec.aliases.is_empty() {
+                    println!("Aliases: {}", spec.aliases.join(", "));
+                }
+                println!("{}", spec.description);
+            }
+            None => println!("Error: unknown command '{topic}'"),
+        },
+    }
+}
+
+/// Print memory words, or a register
+fn examine(computer: &BlueComputer, location: Location, count: u16) {
+    match location {
+        Location::Register(name) => println!("{name}: {:04x}", computer.register(name)),
+        Location::Memory(addr) => {
+            let end = (usize::from(addr) + usize::from(count.max(1))).min(RAM_LENGTH);
+            for (i, word) in computer.ram()[usize::from(addr)..end].iter().enumerate() {
+                println!("{:04x}: {word:04x}", usize::from(addr) + i);
+            }
+        }
+    }
+}
+
+/// Store values in memory from an address, or in a register
+fn deposit(computer: &mut BlueComputer, location: Location, values: &[u16]) {
+    match location {
+        Location::Register(name) => {
+            computer.set_register(name, values[0]);
+            println!("{name}: {:04x}", values[0]);
+        }
+        Location::Memory(addr) => {
+            if usize::from(addr) + values.len() > RAM_LENGTH {
+                println!("Error: deposit runs past the end of memory");
+                return;
+            }
+            for (addr, &value) in (addr..).zip(values) {
+                computer.write_memory(addr, value);
+            }
+            println!("Deposited {} words at {addr:04x}", values.len());
+        }
+    }
+}
+
+/// Display all register values in hexadecimal
+pub(crate) fn print_registers(computer: &BlueComputer) {
+    let register = |name| computer.register(name);
+    let flags = if computer.profile().has_flags() {
+        let set: String = ['Z', 'C', 'V', 'N']
+            .iter()
+            .enumerate()
+            .map(|(bit, &name)| {
+                if register(RegisterName::Flags) & (1 << bit) != 0 {
+                    name
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        format!(" FLAGS: {set}")
+    } else {
+        String::new()
+    };
+    println!(
+        "PC: {:04x} A: {:04x} IR: {:04x} Z: {:04x} MAR: {:04x} MBR: {:04x} DSL: {:02x} DIL: {:02x} DOL: {:02x}{flags}",
+        register(RegisterName::Pc),
+        register(RegisterName::A),
+        register(RegisterName::Ir),
+        register(RegisterName::Z),
+        register(RegisterName::Mar),
+        register(RegisterName::Mbr),
+        register(RegisterName::Dsl) & 0x00FF,
+        register(RegisterName::Dil) & 0x00FF,
+        register(RegisterName::Dol) & 0x00FF
+    );
+    if computer.profile().has_extended_opcodes() {
+        let pending = match computer.bus().pending_interrupt() {
+            Some(device) => format!("device {device:02x}"),
+            None => "none".to_string(),
+        };
+        println!(
+            "Interrupts: {} IPC: {:04x} Pending: {pending}",
+            if computer.interrupts_enabled() {
+                "on"
+            } else {
+                "off"
+            },
+            register(RegisterName::Ipc)
+        );
+    }
+}
+
+/// Display the entire RAM contents
+fn print_memory(computer: &BlueComputer) {
+    println!("==== RAM ====\n0000: ");
+    for (i, word) in computer.ram().iter().enumerate() {
+        print!("{word:04x} ");
+        if (i + 1) % 8 == 0 && (i + 1) != RAM_LENGTH {
+            println!("\n{:04x}: ", i + 1);
+        }
+    }
+    println!();
+}
+
+/// Disassemble the instructions starting at `addr`, marking the PC
+fn print_listing(computer: &BlueComputer, addr: BlueRegister) {
+    let start = usize::from(addr);
+    let options = disasm::Options {
+        profile: computer.profile(),
+        ..Default::default()
+    };
+    for line in disasm::disassemble(computer.ram(), start..start + 8, &options) {
+        let marker = if line.address == computer.pc() {
+            ">"
+        } else {
+            " "
+        };
+        println!("{marker} {line}");
+    }
+}
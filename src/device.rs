@@ -0,0 +1,409 @@
+//! # I/O Devices
+//!
+//! INP and OUT latch a 6-bit device number into DSL. The [`DeviceBus`] routes each
+//! transfer to the [`IoDevice`] attached at that selector.
+//!
+//! A device can declare a [`Latency`]: once a transfer starts, the bus waits that
+//! long before the device responds, and INP/OUT spin in their EXECUTE cycles until
+//! it does, as they would on the real machine.
+//!
+//! Devices can also request an interrupt instead of being polled; see
+//! [`IoDevice::interrupt_requested`].
+
+use std::{any::Any, fmt, io, str::FromStr};
+
+/// Number of device selectors addressable through DSL
+pub const DEVICE_COUNT: usize = 64;
+
+/// A peripheral attached to the Blue I/O bus
+pub trait IoDevice: Any {
+    /// Supply a byte for INP
+    fn read_byte(&mut self) -> io::Result<u8> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Accept a byte from OUT
+    fn write_byte(&mut self, _byte: u8) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Whether the device can complete an INP (`input`) or OUT transfer now (busy
+    /// devices return `false`)
+    fn ready(&self, _input: bool) -> bool {
+        true
+    }
+
+    /// How long the device takes to respond once an INP (`input`) or OUT transfer
+    /// starts
+    fn latency(&self, _input: bool) -> Latency {
+        Latency::Immediate
+    }
+
+    /// Called once per processor clock tick
+    fn clock(&mut self) {}
+
+    /// Whether the device answers INP; if not, INP on its selector is treated as if
+    /// nothing were attached
+    fn handles_input(&self) -> bool {
+        true
+    }
+
+    /// Whether the device accepts OUT; if not, OUT on its selector is treated as if
+    /// nothing were attached
+    fn handles_output(&self) -> bool {
+        true
+    }
+
+    /// Whether an input device has run out of data
+    ///
+    /// INP from an exhausted device follows the bus's [`EndOfInputPolicy`] instead of
+    /// calling [`read_byte`](Self::read_byte).
+    fn at_end(&self) -> bool {
+        false
+    }
+
+    /// Whether the device is asking the processor for service
+    ///
+    /// The request is level-triggered: it is taken at an instruction boundary while
+    /// interrupts are enabled, and keeps being taken until the device withdraws it,
+    /// typically once the handler has done its INP or OUT.
+    fn interrupt_requested(&self) -> bool {
+        false
+    }
+}
+
+/// Time a device takes to respond to a transfer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Latency {
+    /// The transfer completes at the end of the cycle that started it
+    #[default]
+    Immediate,
+    /// Clock ticks of the processor
+    Ticks(u64),
+    /// Microseconds of emulated time, converted at the bus's clock rate
+    Micros(u64),
+}
+
+impl Latency {
+    /// Length in clock ticks at `clock_rate` ticks per second, rounded up and
+    /// saturating at `u64::MAX`
+    pub fn ticks(self, clock_rate: u64) -> u64 {
+        match self {
+            Self::Immediate => 0,
+            Self::Ticks(ticks) => ticks,
+            Self::Micros(micros) => {
+                let ticks = (u128::from(micros) * u128::from(clock_rate)).div_ceil(1_000_000);
+                u64::try_from(ticks).unwrap_or(u64::MAX)
+            }
+        }
+    }
+}
+
+impl FromStr for Latency {
+    type Err = String;
+
+    /// Parse a number of ticks such as `40`, or microseconds such as `100us`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = match s.strip_suffix("us") {
+            Some(micros) => micros.parse().map(Self::Micros),
+            None => s.parse().map(Self::Ticks),
+        };
+        parsed.map_err(|_| format!("'{s}' is not a latency (ticks, or microseconds ending in us)"))
+    }
+}
+
+/// An input device and an output device sharing one selector
+///
+/// INP goes to `input` and OUT to `output`, e.g. a script of keystrokes alongside a
+/// captured printout.
+#[derive(Debug, Default)]
+pub struct Duplex<I, O> {
+    /// Answers INP
+    pub input: I,
+    /// Accepts OUT
+    pub output: O,
+}
+
+impl<I: IoDevice, O: IoDevice> Duplex<I, O> {
+    /// Pair `input` and `output` on one selector
+    pub const fn new(input: I, output: O) -> Self {
+        Self { input, output }
+    }
+}
+
+impl<I: IoDevice, O: IoDevice> IoDevice for Duplex<I, O> {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        self.input.read_byte()
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.output.write_byte(byte)
+    }
+
+    fn ready(&self, input: bool) -> bool {
+        if input {
+            self.input.ready(true)
+        } else {
+            self.output.ready(false)
+        }
+    }
+
+    fn latency(&self, input: bool) -> Latency {
+        if input {
+            self.input.latency(true)
+        } else {
+            self.output.latency(false)
+        }
+    }
+
+    fn clock(&mut self) {
+        self.input.clock();
+        self.output.clock();
+    }
+
+    fn handles_input(&self) -> bool {
+        self.input.handles_input()
+    }
+
+    fn handles_output(&self) -> bool {
+        self.output.handles_output()
+    }
+
+    fn at_end(&self) -> bool {
+        self.input.at_end()
+    }
+
+    fn interrupt_requested(&self) -> bool {
+        self.input.interrupt_requested() || self.output.interrupt_requested()
+    }
+}
+
+/// What happens when INP/OUT selects a device number with nothing attached
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnattachedPolicy {
+    /// Leave the transfer pending for the host to service
+    #[default]
+    Wait,
+    /// Stop the processor with a fault
+    Fault,
+}
+
+/// What happens when INP reads from a device that has run out of input
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EndOfInputPolicy {
+    /// Leave the transfer pending for the host to service
+    #[default]
+    Wait,
+    /// Halt the processor (`RunState::Halted(EndOfInput)`)
+    Halt,
+    /// Stop the processor with a fault
+    Fault,
+}
+
+/// Devices attached to the processor, indexed by DSL selector
+pub struct DeviceBus {
+    devices: [Option<Box<dyn IoDevice>>; DEVICE_COUNT],
+    unattached: UnattachedPolicy,
+    end_of_input: EndOfInputPolicy,
+    latencies: [Option<Latency>; DEVICE_COUNT],
+    clock_rate: u64,
+    /// Ticks left before the device of the current transfer responds
+    waiting: Option<u64>,
+}
+
+impl DeviceBus {
+    /// Clock ticks per second assumed when converting [`Latency::Micros`]
+    pub const DEFAULT_CLOCK_RATE: u64 = 1_000_000;
+
+    /// Create a bus with no devices attached
+    pub const fn new() -> Self {
+        Self {
+            devices: [const { None }; DEVICE_COUNT],
+            unattached: UnattachedPolicy::Wait,
+            end_of_input: EndOfInputPolicy::Wait,
+            latencies: [None; DEVICE_COUNT],
+            clock_rate: Self::DEFAULT_CLOCK_RATE,
+            waiting: None,
+        }
+    }
+
+    /// Attach `device` at `selector`, returning the device it replaces
+    ///
+    /// # Panics
+    /// Panics if `selector` is not below [`DEVICE_COUNT`].
+    pub fn attach(&mut self, selector: u8, device: impl IoDevice) -> Option<Box<dyn IoDevice>> {
+        self.devices[usize::from(selector)].replace(Box::new(device))
+    }
+
+    /// Remove the device at `selector`
+    pub fn detach(&mut self, selector: u8) -> Option<Box<dyn IoDevice>> {
+        self.devices.get_mut(usize::from(selector))?.take()
+    }
+
+    /// Whether a device is attached at `selector`
+    pub fn is_attached(&self, selector: u8) -> bool {
+        self.devices
+            .get(usize::from(selector))
+            .is_some_and(Option::is_some)
+    }
+
+    /// Borrow the device at `selector` as its concrete type
+    pub fn device<D: IoDevice>(&self, selector: u8) -> Option<&D> {
+        let device: &dyn Any = self.devices.get(usize::from(selector))?.as_deref()?;
+        device.downcast_ref()
+    }
+
+    /// Mutably borrow the device at `selector` as its concrete type
+    pub fn device_mut<D: IoDevice>(&mut self, selector: u8) -> Option<&mut D> {
+        let device: &mut dyn Any = self
+            .devices
+            .get_mut(usize::from(selector))?
+            .as_deref_mut()?;
+        device.downcast_mut()
+    }
+
+    /// Policy applied to transfers on selectors with nothing attached
+    pub const fn unattached_policy(&self) -> UnattachedPolicy {
+        self.unattached
+    }
+
+    /// Set the policy applied to transfers on selectors with nothing attached
+    pub const fn set_unattached_policy(&mut self, policy: UnattachedPolicy) {
+        self.unattached = policy;
+    }
+
+    /// Policy applied to INP from a device that has run out of input
+    pub const fn end_of_input_policy(&self) -> EndOfInputPolicy {
+        self.end_of_input
+    }
+
+    /// Set the policy applied to INP from a device that has run out of input
+    pub const fn set_end_of_input_policy(&mut self, policy: EndOfInputPolicy) {
+        self.end_of_input = policy;
+    }
+
+    /// Latency of INP (`input`) or OUT transfers on `selector`: the override if one
+    /// is set, otherwise the attached device's own
+    pub fn latency(&self, selector: u8, input: bool) -> Latency {
+        let index = usize::from(selector);
+        match (self.latencies.get(index), self.devices.get(index)) {
+            (Some(&Some(latency)), _) => latency,
+            (_, Some(Some(device))) => device.latency(input),
+            _ => Latency::Immediate,
+        }
+    }
+
+    /// Override the latency of whatever is attached at `selector`; `None` goes back
+    /// to the device's own
+    ///
+    /// # Panics
+    /// Panics if `selector` is not below [`DEVICE_COUNT`].
+    pub fn set_latency(&mut self, selector: u8, latency: Option<Latency>) {
+        self.latencies[usize::from(selector)] = latency;
+    }
+
+    /// Clock ticks per second of the processor, used to time [`Latency::Micros`]
+    pub const fn clock_rate(&self) -> u64 {
+        self.clock_rate
+    }
+
+    /// Set the clock ticks per second used to time [`Latency::Micros`]
+    pub const fn set_clock_rate(&mut self, ticks_per_second: u64) {
+        self.clock_rate = ticks_per_second;
+    }
+
+    /// Lowest selector whose device is requesting an interrupt
+    pub fn pending_interrupt(&self) -> Option<u8> {
+        (0..DEVICE_COUNT as u8).find(|&selector| {
+            self.devices[usize::from(selector)]
+                .as_deref()
+                .is_some_and(IoDevice::interrupt_requested)
+        })
+    }
+
+    /// Whether a device at `selector` handles INP (`input`) or OUT transfers
+    pub(crate) fn handles(&self, selector: u8, input: bool) -> bool {
+        self.devices
+            .get(usize::from(selector))
+            .and_then(Option::as_deref)
+            .is_some_and(|device| {
+                if input {
+                    device.handles_input()
+                } else {
+                    device.handles_output()
+                }
+            })
+    }
+
+    /// Whether the device at `selector` handles INP and is out of input
+    pub(crate) fn at_end(&self, selector: u8) -> bool {
+        self.handles(selector, true)
+            && self.devices[usize::from(selector)]
+                .as_deref()
+                .is_some_and(IoDevice::at_end)
+    }
+
+    /// Borrow the device handling an INP (`input`) or OUT transfer on `selector`
+    pub(crate) fn get_mut(&mut self, selector: u8, input: bool) -> Option<&mut dyn IoDevice> {
+        if !self.handles(selector, input) {
+            return None;
+        }
+        self.devices.get_mut(usize::from(selector))?.as_deref_mut()
+    }
+
+    /// Whether the device at `selector` has had its latency to respond to the
+    /// current INP (`input`) or OUT transfer, starting the wait if it is new
+    pub(crate) fn transfer_due(&mut self, selector: u8, input: bool) -> bool {
+        let clock_rate = self.clock_rate;
+        let latency = self.latency(selector, input);
+        *self
+            .waiting
+            .get_or_insert_with(|| latency.ticks(clock_rate))
+            == 0
+    }
+
+    /// Forget the wait for the current transfer once it completes or is abandoned
+    pub(crate) const fn end_transfer(&mut self) {
+        self.waiting = None;
+    }
+
+    /// Deliver a clock tick to every attached device
+    pub(crate) fn clock(&mut self) {
+        if let Some(waiting) = &mut self.waiting {
+            *waiting = waiting.saturating_sub(1);
+        }
+        for device in self.devices.iter_mut().flatten() {
+            device.clock();
+        }
+    }
+
+    /// Deliver `ticks` clock ticks to every attached device
+    pub(crate) fn clock_ticks(&mut self, ticks: u64) {
+        if let Some(waiting) = &mut self.waiting {
+            *waiting = waiting.saturating_sub(ticks);
+        }
+        for device in self.devices.iter_mut().flatten() {
+            for _ in 0..ticks {
+                device.clock();
+            }
+        }
+    }
+}
+
+impl Default for DeviceBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DeviceBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let attached: Vec<usize> = (0..DEVICE_COUNT)
+            .filter(|&i| self.devices[i].is_some())
+            .collect();
+        f.debug_struct("DeviceBus")
+            .field("attached", &attached)
+            .field("unattached", &self.unattached)
+            .finish()
+    }
+}
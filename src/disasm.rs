@@ -0,0 +1,148 @@
+//! # Blue Disassembler
+//!
+//! Turns memory words back into assembler mnemonics. Given an entry point, code is
+//! told apart from data by following every path of execution from it: words never
+//! reached through fall-through, JMP, JMA or SRJ are listed as `DW` data.
+
+use crate::{BlueRegister, Instruction, IsaProfile, OperandKind};
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+/// Disassembly settings
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Where execution starts; `None` lists every word as an instruction
+    pub entry: Option<BlueRegister>,
+    /// Label names for addresses, as produced by the assembler
+    pub symbols: BTreeMap<String, BlueRegister>,
+    /// Instruction set to decode
+    pub profile: IsaProfile,
+}
+
+/// One annotated line of a listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingLine {
+    /// Memory address
+    pub address: BlueRegister,
+    /// Word stored at the address
+    pub word: u16,
+    /// Label defined at the address
+    pub label: Option<String>,
+    /// Whether the word was judged to be an instruction
+    pub is_code: bool,
+    /// Mnemonic and operand, or a `DW` directive for data
+    pub text: String,
+}
+
+impl fmt::Display for ListingLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = self
+            .label
+            .as_ref()
+            .map_or_else(String::new, |label| format!("{label}:"));
+        write!(
+            f,
+            "{:03X}  {:04X}  {label:<12}{}",
+            self.address, self.word, self.text
+        )
+    }
+}
+
+/// Render a single word as an instruction, naming operands found in `labels`
+///
+/// Words that do not decode, or that set operand bits of an instruction taking
+/// none, are rendered as data.
+pub fn format_instruction(
+    word: u16,
+    profile: IsaProfile,
+    labels: &BTreeMap<BlueRegister, String>,
+) -> String {
+    let Ok(instruction) = Instruction::decode(word, profile) else {
+        return format_data(word);
+    };
+    let operand = instruction.operand(word);
+    let mnemonic = instruction.mnemonic();
+    match instruction.operand_kind() {
+        OperandKind::None if operand == 0 => mnemonic.to_string(),
+        // The assembler cannot produce an operand here, so it must be data
+        OperandKind::None => format_data(word),
+        OperandKind::Device => format!("{mnemonic} 0x{operand:02X}"),
+        OperandKind::Address => match labels.get(&operand) {
+            Some(label) => format!("{mnemonic} {label}"),
+            None if instruction.is_extended() => format!("{mnemonic} 0x{operand:02X}"),
+            None => format!("{mnemonic} 0x{operand:03X}"),
+        },
+    }
+}
+
+/// Render a word as a data directive
+fn format_data(word: u16) -> String {
+    format!("DW 0x{word:04X}")
+}
+
+/// Mark every word reachable from `entry` by following execution paths
+pub fn find_code(memory: &[u16], entry: BlueRegister, profile: IsaProfile) -> Vec<bool> {
+    let mut code = vec![false; memory.len()];
+    let mut pending = vec![usize::from(entry)];
+
+    while let Some(addr) = pending.pop() {
+        let Some(&word) = memory.get(addr) else {
+            continue;
+        };
+        if code[addr] {
+            continue;
+        }
+        code[addr] = true;
+
+        let Ok(instruction) = Instruction::decode(word, profile) else {
+            continue;
+        };
+        let target = usize::from(instruction.operand(word));
+        match instruction {
+            Instruction::Hlt | Instruction::Rti => (),
+            Instruction::Jmp => pending.push(target),
+            Instruction::Jma
+            | Instruction::Jmz
+            | Instruction::Jmc
+            | Instruction::Jmv
+            | Instruction::Srj => {
+                pending.push(target);
+                pending.push(addr + 1);
+            }
+            _ => pending.push(addr + 1),
+        }
+    }
+    code
+}
+
+/// Produce a listing of `range` within `memory`
+pub fn disassemble(memory: &[u16], range: Range<usize>, options: &Options) -> Vec<ListingLine> {
+    let labels: BTreeMap<BlueRegister, String> = options
+        .symbols
+        .iter()
+        .map(|(name, &addr)| (addr, name.clone()))
+        .collect();
+    let code = options
+        .entry
+        .map(|entry| find_code(memory, entry, options.profile));
+    let end = range.end.min(memory.len());
+
+    memory[range.start.min(end)..end]
+        .iter()
+        .zip(range.start..)
+        .filter_map(|(&word, addr)| {
+            let address = BlueRegister::try_from(addr).ok()?;
+            let is_code = code.as_ref().is_none_or(|code| code[addr]);
+            Some(ListingLine {
+                address,
+                word,
+                label: labels.get(&address).cloned(),
+                is_code,
+                text: if is_code {
+                    format_instruction(word, options.profile, &labels)
+                } else {
+                    format_data(word)
+                },
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,81 @@
+//! # Explain Mode
+//!
+//! Narration of the register transfers behind each instruction, for teaching.
+//! While explain mode is on, the sequencer records a [`MicroEvent`] for every
+//! micro-operation of the microcode, including guarded ones it skipped. Events
+//! hold the values involved rather than text, so a front end can lay them out as
+//! it likes; their `Display` is a sentence such as
+//! `tick 4 (FETCH): MBR ← RAM[MAR=0x012] = 0x6010`.
+
+use crate::{
+    BlueRegister, MicroTick,
+    microcode::{Condition, MicroOp},
+};
+use std::fmt;
+
+/// Names of the processor flags, by bit
+const FLAG_NAMES: [&str; 4] = ["zero", "carry", "overflow", "negative"];
+
+/// A micro-operation the sequencer reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MicroEvent {
+    /// The clock pulse, with the opcode of its instruction
+    pub tick: MicroTick,
+    /// The operation
+    pub op: MicroOp,
+    /// Guard on the operation, if any
+    pub condition: Option<Condition>,
+    /// Whether the operation ran, `false` if its guard did not hold
+    pub performed: bool,
+    /// The MAR, for operations that read or write memory
+    pub address: Option<BlueRegister>,
+    /// Value the operation stored in its destination
+    pub value: Option<u16>,
+    /// Flags the operation set, as bits of the flags register
+    pub flags_set: BlueRegister,
+    /// Flags the operation cleared
+    pub flags_cleared: BlueRegister,
+}
+
+impl fmt::Display for MicroEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut transfer = self
+            .op
+            .notation()
+            .replace("<-", "←")
+            .replace('+', " + ")
+            .replace('-', " - ")
+            .replace('^', " ^ ")
+            .replace('&', " & ")
+            .replace('|', " | ")
+            .replace('←', " ← ");
+        if let Some(address) = self.address {
+            transfer = transfer.replace("[MAR]", &format!("[MAR=0x{address:03x}]"));
+        }
+        write!(
+            f,
+            "tick {} ({}): ",
+            self.tick.tick,
+            self.tick.state.to_string().to_ascii_uppercase()
+        )?;
+        if let Some(condition) = self.condition {
+            write!(f, "[{}] ", condition.notation())?;
+        }
+        f.write_str(&transfer)?;
+        if !self.performed {
+            return write!(f, ", skipped");
+        }
+        if let Some(value) = self.value {
+            write!(f, " = 0x{value:04x}")?;
+        }
+        for (bit, name) in FLAG_NAMES.iter().enumerate() {
+            if self.flags_set & (1 << bit) != 0 {
+                write!(f, ", {name} set")?;
+            }
+            if self.flags_cleared & (1 << bit) != 0 {
+                write!(f, ", {name} cleared")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! # Scripted Input
+//!
+//! [`ScriptedInput`] is an input device that answers INP from a prepared byte
+//! sequence instead of the console prompt, so programs that read input can run
+//! unattended. The bytes can come from a string, a file or any reader such as a pipe,
+//! either raw or as hex tokens. When the script runs out the bus applies its
+//! [`EndOfInputPolicy`](crate::device::EndOfInputPolicy).
+//!
+//! ```
+//! use blue::{BlueComputer, StopReason, input::ScriptedInput};
+//!
+//! let mut computer = BlueComputer::new();
+//! computer.bus_mut().attach(0, ScriptedInput::from_hex("2a").unwrap());
+//! computer.load_program(&[0xB000, 0x0000]); // INP 0, HLT
+//! computer.press_on();
+//! assert_eq!(computer.run_until(100), StopReason::Halted);
+//! assert_eq!(computer.a(), 0x2A00);
+//! ```
+
+use crate::device::IoDevice;
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Read},
+    path::Path,
+    str::FromStr,
+};
+
+/// How input text is turned into bytes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Every byte is delivered as is
+    Raw,
+    /// Whitespace-separated hex bytes, as typed at the console prompt; `#` starts a
+    /// comment
+    #[default]
+    Hex,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "raw" => Ok(Self::Raw),
+            "hex" => Ok(Self::Hex),
+            _ => Err(format!("unknown input format '{s}' (raw, hex)")),
+        }
+    }
+}
+
+/// An input device fed from a prepared byte sequence
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    bytes: VecDeque<u8>,
+    interrupts: bool,
+}
+
+impl ScriptedInput {
+    /// Deliver `bytes` in order
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            bytes: bytes.into().into(),
+            interrupts: false,
+        }
+    }
+
+    /// Parse whitespace-separated hex bytes such as `"48 65 6c"`
+    ///
+    /// # Errors
+    /// Returns an error naming the first token that is not a hex byte.
+    pub fn from_hex(text: &str) -> io::Result<Self> {
+        let bytes = text
+            .lines()
+            .flat_map(|line| {
+                line.split('#')
+                    .next()
+                    .unwrap_or_default()
+                    .split_whitespace()
+            })
+            .map(|token| {
+                let digits = token
+                    .strip_prefix("0x")
+                    .or_else(|| token.strip_prefix("0X"))
+                    .unwrap_or(token);
+                u8::from_str_radix(digits, 16).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("'{token}' is not a hex byte"),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<u8>>>()?;
+        Ok(Self::new(bytes))
+    }
+
+    /// Read all of `reader`, e.g. standard input, in the given format
+    ///
+    /// # Errors
+    /// Fails if reading fails or hex input is malformed.
+    pub fn from_reader(mut reader: impl Read, format: InputFormat) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        match format {
+            InputFormat::Raw => Ok(Self::new(bytes)),
+            InputFormat::Hex => {
+                let text = String::from_utf8(bytes)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                Self::from_hex(&text)
+            }
+        }
+    }
+
+    /// Read the file at `path` in the given format
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or hex input is malformed.
+    pub fn from_file(path: impl AsRef<Path>, format: InputFormat) -> io::Result<Self> {
+        Self::from_reader(fs::File::open(path)?, format)
+    }
+
+    /// Queue more bytes after those not yet read
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.bytes.extend(bytes);
+    }
+
+    /// Number of bytes not yet read
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Request an interrupt whenever a byte is waiting to be read
+    pub const fn set_interrupts(&mut self, enabled: bool) {
+        self.interrupts = enabled;
+    }
+}
+
+impl IoDevice for ScriptedInput {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        self.bytes
+            .pop_front()
+            .ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+    }
+
+    fn handles_output(&self) -> bool {
+        false
+    }
+
+    fn at_end(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn interrupt_requested(&self) -> bool {
+        self.interrupts && !self.bytes.is_empty()
+    }
+}
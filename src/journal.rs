@@ -0,0 +1,146 @@
+//! # History Journal
+//!
+//! A bounded record of what the processor changed, kept so execution can be run
+//! backwards. Each entry holds the registers as they were before one instruction
+//! (or one tick) and the RAM words it overwrote; undoing an entry puts both back.
+//! Once the journal holds `depth` entries the oldest are dropped.
+//!
+//! Only the processor is rewound: bytes already exchanged with I/O devices stay
+//! consumed or written.
+
+use crate::{BlueRegister, blue::Registers};
+use std::collections::VecDeque;
+
+/// How much each journal entry covers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// One entry per instruction
+    #[default]
+    Instruction,
+    /// One entry per clock tick, so single ticks can be undone too
+    Tick,
+}
+
+/// A RAM word overwritten by the processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteRecord {
+    /// Address of the instruction that wrote it
+    pub instruction: BlueRegister,
+    /// Address written
+    pub address: BlueRegister,
+    /// Value before the write
+    pub old: u16,
+    /// Value written
+    pub new: u16,
+}
+
+/// Changes made by one instruction or tick
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    /// Address of the instruction the step belongs to
+    instruction: BlueRegister,
+    /// Registers before the step
+    pub(crate) before: Registers,
+    /// RAM writes during the step, in order
+    pub(crate) writes: Vec<WriteRecord>,
+}
+
+/// Bounded history of processor changes
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    depth: usize,
+    granularity: Granularity,
+    entries: VecDeque<Entry>,
+}
+
+impl Journal {
+    /// A journal keeping the last `depth` entries; a depth of 0 records nothing
+    pub fn new(depth: usize, granularity: Granularity) -> Self {
+        Self {
+            depth,
+            granularity,
+            ..Self::default()
+        }
+    }
+
+    /// Maximum number of entries kept
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// What each entry covers
+    pub const fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    /// Number of entries available to undo
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there is nothing to undo
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget all recorded history
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// RAM writes still in the journal, most recent first
+    pub fn writes(&self) -> impl Iterator<Item = &WriteRecord> {
+        self.entries
+            .iter()
+            .rev()
+            .flat_map(|entry| entry.writes.iter().rev())
+    }
+
+    /// The most recent write to `address` still in the journal
+    pub fn last_write(&self, address: BlueRegister) -> Option<&WriteRecord> {
+        self.writes().find(|write| write.address == address)
+    }
+
+    /// Whether a step starting at `clock_pulse` of FETCH or EXECUTE gets an entry
+    pub(crate) const fn starts_entry(&self, execute: bool, clock_pulse: u8) -> bool {
+        self.depth > 0
+            && match self.granularity {
+                Granularity::Instruction => !execute && clock_pulse == 0,
+                Granularity::Tick => true,
+            }
+    }
+
+    /// Open a new entry for a step starting from `before`
+    pub(crate) fn begin(&mut self, before: Registers) {
+        // A new instruction is fetched from MAR; later ticks belong to the same one
+        let instruction = match self.entries.back() {
+            Some(entry) if before.execute || before.clock_pulse != 0 => entry.instruction,
+            _ => before.mar,
+        };
+        if self.entries.len() == self.depth {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Entry {
+            instruction,
+            before,
+            writes: Vec::new(),
+        });
+    }
+
+    /// Note that `address` changed from `old` to `new` in the current entry
+    pub(crate) fn record_write(&mut self, address: BlueRegister, old: u16, new: u16) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.writes.push(WriteRecord {
+                instruction: entry.instruction,
+                address,
+                old,
+                new,
+            });
+        }
+    }
+
+    /// Take the most recent entry off the journal
+    pub(crate) fn pop(&mut self) -> Option<Entry> {
+        self.entries.pop_back()
+    }
+}
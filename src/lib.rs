@@ -0,0 +1,35 @@
+//! # Blue
+//!
+//! Library interface to the Blue computer emulator, for embedding the machine in
+//! test harnesses and other tooling.
+//!
+//! ```
+//! use blue::{BlueComputer, StopReason};
+//!
+//! let mut computer = BlueComputer::new();
+//! computer.load_program(&[0x6003, 0x1004, 0x0000, 0x0005, 0x0003]); // LDA, ADD, HLT
+//! computer.press_on();
+//! assert_eq!(computer.run_until(100), StopReason::Halted);
+//! assert_eq!(computer.a(), 0x0008);
+//! ```
+
+pub mod asm;
+mod blue;
+pub mod breakpoint;
+pub mod debugger;
+pub mod device;
+pub mod disasm;
+pub mod explain;
+pub mod input;
+pub mod journal;
+pub mod microcode;
+pub mod output;
+pub mod snapshot;
+pub mod tape;
+pub mod teletype;
+
+pub use blue::{
+    BlueComputer, BlueRegister, ExecutionMode, Fault, FaultKind, HaltReason, INTERRUPT_VECTOR,
+    Instruction, IoRequest, IsaProfile, MicroTick, OperandKind, OverflowPolicy, PauseReason,
+    RAM_LENGTH, RegisterName, RunState, State, StopReason,
+};
@@ -1,7 +1,34 @@
-use crate::blue::{BlueComputer, RAM_LENGTH};
-use std::{env, fs::File, io::Read, path::Path};
+use blue::{
+    BlueComputer, BlueRegister, ExecutionMode, IsaProfile, PauseReason, RAM_LENGTH, asm,
+    debugger::Debugger,
+    device::{DEVICE_COUNT, DeviceBus, Duplex, EndOfInputPolicy, Latency},
+    disasm,
+    input::{InputFormat, ScriptedInput},
+    journal::Granularity,
+    microcode::Microcode,
+    output::{OutputSink, Rendering},
+    snapshot::Snapshot,
+    tape::{TapePunch, TapeReader},
+    teletype::{LineEnding, Teletype},
+};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-mod blue;
+const TEST_PROGRAMS: [(&str, &str); 7] = [
+    ("add", "add_sub_test.bin"),
+    ("logic", "logic_test.bin"),
+    ("jump", "jump_test.bin"),
+    ("shift", "shift_test.bin"),
+    ("io", "io_test.bin"),
+    ("cmp", "cmp_test.bin"),
+    ("combined", "combined_test.bin"),
+];
 
 fn load_program_file(filename: &str) -> Vec<u16> {
     let path = Path::new("progs").join(filename);
@@ -19,67 +46,643 @@ fn load_program_file(filename: &str) -> Vec<u16> {
         .collect()
 }
 
-fn main() {
-    println!("Running blue emulator");
+/// `blue asm <source> [-o <output>] [--hex]`
+fn assemble_command(args: &[String]) -> ExitCode {
+    let mut source = None;
+    let mut output = None;
+    let mut hex = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => output = args.next().map(PathBuf::from),
+            "--hex" => hex = true,
+            _ => source = Some(PathBuf::from(arg)),
+        }
+    }
+    let Some(source) = source else {
+        println!("Usage: blue asm <source> [-o <output>] [--hex]");
+        return ExitCode::FAILURE;
+    };
+    let output = output.unwrap_or_else(|| source.with_extension(if hex { "hex" } else { "img" }));
 
-    let test_programs = [
-        ("add", "add_sub_test.bin"),
-        ("logic", "logic_test.bin"),
-        ("jump", "jump_test.bin"),
-        ("shift", "shift_test.bin"),
-        ("io", "io_test.bin"),
-        ("cmp", "cmp_test.bin"),
-        ("combined", "combined_test.bin"),
-    ];
+    let text = match fs::read_to_string(&source) {
+        Ok(text) => text,
+        Err(e) => {
+            println!("Failed to read {}: {e}", source.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let assembly = match asm::assemble(&text) {
+        Ok(assembly) => assembly,
+        Err(errors) => {
+            for e in errors {
+                println!("{}:{e}", source.display());
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
-    let args: Vec<String> = env::args().collect();
-    let mut program_data = [0u16; RAM_LENGTH];
-
-    if args.len() >= 2 {
-        let test_name = &args[1];
-        if let Some((_, filename)) = test_programs.iter().find(|(name, _)| name == test_name) {
-            println!("Running test program: {test_name}");
-            let test_program = load_program_file(filename);
-            program_data[..test_program.len()].copy_from_slice(&test_program);
-        } else {
-            let mut file = match File::open(&args[1]) {
-                Ok(f) => f,
-                Err(e) => {
-                    println!("Failed to open program file: {e}");
-                    println!("Available test programs:");
-                    for (name, _) in &test_programs {
-                        println!("  {name}");
-                    }
-                    return;
-                }
-            };
+    let written = if hex {
+        fs::write(&output, assembly.to_hex() + "\n")
+    } else {
+        fs::write(&output, assembly.to_le_bytes())
+    };
+    if let Err(e) = written {
+        println!("Failed to write {}: {e}", output.display());
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "Assembled {} words to {}",
+        assembly.words.len(),
+        output.display()
+    );
+    ExitCode::SUCCESS
+}
+
+/// A program ready to load, with any labels known from its source
+struct Program {
+    words: Vec<u16>,
+    symbols: BTreeMap<String, BlueRegister>,
+}
+
+/// Load a named test program, `.asm` source or little-endian image
+fn load_program(arg: &str) -> Result<Program, String> {
+    if let Some((_, filename)) = TEST_PROGRAMS.iter().find(|(name, _)| *name == arg) {
+        return Ok(Program {
+            words: load_program_file(filename),
+            symbols: BTreeMap::new(),
+        });
+    }
+
+    if Path::new(arg).extension().is_some_and(|ext| ext == "asm") {
+        let text =
+            fs::read_to_string(arg).map_err(|e| format!("Failed to open program file: {e}"))?;
+        let assembly = asm::assemble(&text).map_err(|errors| {
+            let messages: Vec<String> = errors.iter().map(|e| format!("{arg}:{e}")).collect();
+            messages.join("\n")
+        })?;
+        return Ok(Program {
+            words: assembly.words,
+            symbols: assembly.symbols,
+        });
+    }
+
+    let mut file = File::open(arg).map_err(|e| format!("Failed to open program file: {e}"))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read program file: {e}"))?;
 
-            let mut buffer = Vec::new();
-            if let Err(e) = file.read_to_end(&mut buffer) {
-                println!("Failed to read program file: {e}");
-                return;
+    let words = buffer
+        .chunks(2)
+        .take(RAM_LENGTH)
+        .map(|chunk| {
+            if chunk.len() == 2 {
+                u16::from_le_bytes([chunk[0], chunk[1]])
+            } else {
+                u16::from_le_bytes([chunk[0], 0])
             }
+        })
+        .collect();
+    Ok(Program {
+        words,
+        symbols: BTreeMap::new(),
+    })
+}
+
+/// Parse an address given as `0x` hex or decimal
+fn parse_address(text: &str) -> Option<BlueRegister> {
+    match text.strip_prefix("0x") {
+        Some(hex) => BlueRegister::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
 
-            for (i, chunk) in buffer.chunks(2).enumerate() {
-                if i >= RAM_LENGTH {
-                    break;
+/// Parse the value given to `--profile`
+fn parse_profile(name: Option<&String>) -> Result<IsaProfile, String> {
+    name.ok_or_else(|| "--profile needs a name".to_string())?
+        .parse()
+}
+
+/// `blue disasm <test_name|file> [--entry <addr>] [--all] [--profile <isa>]`
+fn disassemble_command(args: &[String]) -> ExitCode {
+    let mut source = None;
+    let mut options = disasm::Options {
+        entry: Some(0),
+        ..Default::default()
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--entry" => match args.next().and_then(|addr| parse_address(addr)) {
+                Some(entry) => options.entry = Some(entry),
+                None => {
+                    println!("--entry needs an address");
+                    return ExitCode::FAILURE;
                 }
-                program_data[i] = if chunk.len() == 2 {
-                    u16::from_le_bytes([chunk[0], chunk[1]])
-                } else {
-                    u16::from_le_bytes([chunk[0], 0])
+            },
+            "--all" => options.entry = None,
+            "--profile" => match parse_profile(args.next()) {
+                Ok(profile) => options.profile = profile,
+                Err(e) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ => source = Some(arg),
+        }
+    }
+    let Some(source) = source else {
+        println!("Usage: blue disasm <test_name|file> [--entry <addr>] [--all] [--profile <isa>]");
+        return ExitCode::FAILURE;
+    };
+
+    let program = match load_program(source) {
+        Ok(program) => program,
+        Err(e) => {
+            println!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    options.symbols = program.symbols;
+    for line in disasm::disassemble(&program.words, 0..program.words.len(), &options) {
+        println!("{line}");
+    }
+    ExitCode::SUCCESS
+}
+
+/// Parse a DSL device number, decimal or `0x` hex
+fn parse_device(text: &str) -> Option<u8> {
+    parse_address(text)
+        .and_then(|device| u8::try_from(device).ok())
+        .filter(|&device| usize::from(device) < DEVICE_COUNT)
+}
+
+/// Parse `<device>=<latency>` as given to `--latency`
+fn parse_latency(arg: &str) -> Result<(u8, Latency), String> {
+    let (device, latency) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("--latency needs <device>=<time>, not '{arg}'"))?;
+    let device = parse_device(device)
+        .ok_or_else(|| format!("--latency needs a device number below {DEVICE_COUNT}"))?;
+    Ok((device, latency.parse()?))
+}
+
+/// Apply the microcode file at `path` on top of the built-in table
+fn load_microcode(path: Option<&String>) -> Result<Microcode, String> {
+    let path = path.ok_or_else(|| "--microcode needs a file".to_string())?;
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    let mut microcode = (*Microcode::builtin()).clone();
+    microcode
+        .overlay(&text)
+        .map_err(|e| format!("{path}: {e}"))?;
+    Ok(microcode)
+}
+
+/// `blue resume <snapshot> [--fast] [--commands <file>]`
+fn resume_command(args: &[String]) -> ExitCode {
+    let mut source = None;
+    let mut mode = ExecutionMode::default();
+    let mut debugger = Debugger::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fast" => mode = ExecutionMode::Fast,
+            "--commands" => {
+                let Some(path) = args.next() else {
+                    println!("--commands needs a file of debugger commands");
+                    return ExitCode::FAILURE;
                 };
+                if let Err(e) = debugger.queue_script(path) {
+                    println!("Failed to read {path}: {e}");
+                    return ExitCode::FAILURE;
+                }
             }
+            _ => source = Some(arg),
         }
-    } else {
-        println!("No program specified. Available test programs:");
-        for (name, _) in &test_programs {
-            println!("  {name}");
+    }
+    let Some(source) = source else {
+        println!("Usage: blue resume <snapshot> [--fast] [--commands <file>]");
+        return ExitCode::FAILURE;
+    };
+    let snapshot = match Snapshot::read_file(source) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            println!("Failed to read {source}: {e}");
+            return ExitCode::FAILURE;
         }
-        println!("Usage: {} <test_name|file>", args[0]);
-        return;
+    };
+
+    let mut computer = BlueComputer::new();
+    computer.set_execution_mode(mode);
+    computer.load_state(&snapshot);
+    computer.run_console(&mut debugger);
+    ExitCode::SUCCESS
+}
+
+fn print_usage(program: &str) {
+    println!("Available test programs:");
+    for (name, _) in &TEST_PROGRAMS {
+        println!("  {name}");
+    }
+    println!("Usage: {program} <test_name|file> [run options]");
+    println!("       {program} asm <source> [-o <output>] [--hex]");
+    println!("       {program} disasm <test_name|file> [--entry <addr>] [--all] [--profile <isa>]");
+    println!("       {program} microcode");
+    println!("       {program} resume <snapshot> [--fast] [--commands <file>]");
+    println!("Run options:");
+    for (option, description) in [
+        (
+            "--profile <isa>",
+            "instruction set: foster, flags or extended",
+        ),
+        (
+            "--microcode <file>",
+            "load microcode over the built-in table",
+        ),
+        ("--fast", "execute whole instructions instead of ticks"),
+        (
+            "--on-overflow <policy>",
+            "halt, flag, wrap, fault or trap:<addr> on ADD/SUB overflow",
+        ),
+        ("--history <n>", "keep n instructions for reverse execution"),
+        (
+            "--input <file|->",
+            "answer INP from a file or standard input; with -, the console reads the terminal",
+        ),
+        (
+            "--input-format raw|hex",
+            "how the input file is read (default hex)",
+        ),
+        (
+            "--input-device <n>",
+            "device number the input is attached to (default 0)",
+        ),
+        (
+            "--on-eof wait|halt|fault",
+            "what INP does once the input runs out",
+        ),
+        (
+            "--output <file|->",
+            "send OUT bytes to a file or standard output",
+        ),
+        (
+            "--output-format <format>",
+            "raw, ascii, hex or decimal (default hex)",
+        ),
+        (
+            "--output-device <n>",
+            "device number the output is attached to (default 0)",
+        ),
+        ("--quiet", "do not print the registers after every cycle"),
+        ("--explain", "narrate every micro-operation as it runs"),
+        (
+            "--commands <file>",
+            "run debugger commands before the first instruction",
+        ),
+        (
+            "--teletype <n>",
+            "attach a teletype on the terminal at device n",
+        ),
+        (
+            "--teletype-upper",
+            "7-bit upper-case teletype, like a Model 33",
+        ),
+        ("--teletype-paced", "print at 10 characters per second"),
+        (
+            "--teletype-lf",
+            "LF starts a new line and Return reads as LF",
+        ),
+        ("--reader <file>", "load a paper tape into the tape reader"),
+        (
+            "--reader-device <n>",
+            "device number of the tape reader (default 1)",
+        ),
+        ("--punch <file>", "append punched tape to a file"),
+        (
+            "--punch-device <n>",
+            "device number of the tape punch (default 2)",
+        ),
+        (
+            "--latency <n>=<time>",
+            "device n responds after ticks, or microseconds with us",
+        ),
+        (
+            "--clock-rate <hz>",
+            "clock ticks per second for microsecond latencies",
+        ),
+    ] {
+        println!("  {option:<26}{description}");
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("asm") => return assemble_command(&args[2..]),
+        Some("disasm") => return disassemble_command(&args[2..]),
+        Some("resume") => return resume_command(&args[2..]),
+        Some("microcode") => {
+            print!("{}", Microcode::builtin());
+            return ExitCode::SUCCESS;
+        }
+        _ => (),
+    }
+
+    println!("Running blue emulator");
+
+    let mut program_arg = None;
+    let mut profile = IsaProfile::default();
+    let mut microcode = None;
+    let mut mode = ExecutionMode::default();
+    let mut overflow = None;
+    let mut history = 0;
+    let mut input = None;
+    let mut input_format = InputFormat::default();
+    let mut input_device = 0;
+    let mut end_of_input = EndOfInputPolicy::default();
+    let mut output = None;
+    let mut rendering = Rendering::default();
+    let mut output_device = 0;
+    let mut quiet = false;
+    let mut explain = false;
+    let mut teletype_device = None;
+    let mut teletype_upper = false;
+    let mut teletype_paced = false;
+    let mut teletype_line_ending = LineEnding::default();
+    let mut reader = None;
+    let mut reader_device = 1;
+    let mut punch = None;
+    let mut punch_device = 2;
+    let mut latencies = Vec::new();
+    let mut clock_rate = DeviceBus::DEFAULT_CLOCK_RATE;
+    let mut commands = None;
+    let mut run_args = args[1..].iter();
+    while let Some(arg) = run_args.next() {
+        match arg.as_str() {
+            "--profile" => match parse_profile(run_args.next()) {
+                Ok(isa) => profile = isa,
+                Err(e) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--microcode" => match load_microcode(run_args.next()) {
+                Ok(table) => microcode = Some(table),
+                Err(e) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--fast" => mode = ExecutionMode::Fast,
+            "--on-overflow" => match run_args.next().map(|policy| policy.parse()) {
+                Some(Ok(policy)) => overflow = Some(policy),
+                Some(Err(e)) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+                None => {
+                    println!("--on-overflow needs halt, flag, wrap, fault or trap:<addr>");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--history" => match run_args.next().and_then(|depth| depth.parse().ok()) {
+                Some(depth) => history = depth,
+                None => {
+                    println!("--history needs a number of instructions");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--input" => input = run_args.next(),
+            "--input-format" => match run_args.next().map(|format| format.parse()) {
+                Some(Ok(format)) => input_format = format,
+                Some(Err(e)) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+                None => {
+                    println!("--input-format needs raw or hex");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--input-device" => match run_args.next().and_then(|n| parse_device(n)) {
+                Some(device) => input_device = device,
+                None => {
+                    println!("--input-device needs a device number below {DEVICE_COUNT}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--on-eof" => match run_args.next().map(String::as_str) {
+                Some("wait") => end_of_input = EndOfInputPolicy::Wait,
+                Some("halt") => end_of_input = EndOfInputPolicy::Halt,
+                Some("fault") => end_of_input = EndOfInputPolicy::Fault,
+                _ => {
+                    println!("--on-eof needs wait, halt or fault");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--output" => output = run_args.next(),
+            "--output-format" => match run_args.next().map(|format| format.parse()) {
+                Some(Ok(format)) => rendering = format,
+                Some(Err(e)) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+                None => {
+                    println!("--output-format needs raw, ascii, hex or decimal");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--output-device" => match run_args.next().and_then(|n| parse_device(n)) {
+                Some(device) => output_device = device,
+                None => {
+                    println!("--output-device needs a device number below {DEVICE_COUNT}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--quiet" => quiet = true,
+            "--explain" => explain = true,
+            "--commands" => match run_args.next() {
+                Some(path) => commands = Some(path),
+                None => {
+                    println!("--commands needs a file of debugger commands");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--teletype" => match run_args.next().and_then(|n| parse_device(n)) {
+                Some(device) => teletype_device = Some(device),
+                None => {
+                    println!("--teletype needs a device number below {DEVICE_COUNT}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--teletype-upper" => teletype_upper = true,
+            "--teletype-paced" => teletype_paced = true,
+            "--teletype-lf" => teletype_line_ending = LineEnding::Newline,
+            "--reader" => reader = run_args.next(),
+            "--reader-device" => match run_args.next().and_then(|n| parse_device(n)) {
+                Some(device) => reader_device = device,
+                None => {
+                    println!("--reader-device needs a device number below {DEVICE_COUNT}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--punch" => punch = run_args.next(),
+            "--punch-device" => match run_args.next().and_then(|n| parse_device(n)) {
+                Some(device) => punch_device = device,
+                None => {
+                    println!("--punch-device needs a device number below {DEVICE_COUNT}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--latency" => match run_args.next().map(|arg| parse_latency(arg)) {
+                Some(Ok(latency)) => latencies.push(latency),
+                Some(Err(e)) => {
+                    println!("{e}");
+                    return ExitCode::FAILURE;
+                }
+                None => {
+                    println!("--latency needs <device>=<ticks> or <device>=<micros>us");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--clock-rate" => match run_args.next().and_then(|hz| hz.parse().ok()) {
+                Some(hz) if hz > 0 => clock_rate = hz,
+                _ => {
+                    println!("--clock-rate needs a number of ticks per second");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ => program_arg = Some(arg),
+        }
+    }
+    let Some(arg) = program_arg else {
+        println!("No program specified.");
+        print_usage(&args[0]);
+        return ExitCode::FAILURE;
+    };
+    let program = match load_program(arg) {
+        Ok(program) => program,
+        Err(e) => {
+            println!("{e}");
+            print_usage(&args[0]);
+            return ExitCode::FAILURE;
+        }
+    };
+    if TEST_PROGRAMS.iter().any(|(name, _)| name == arg) {
+        println!("Running test program: {arg}");
     }
 
     let mut computer = BlueComputer::new();
-    computer.run_program(&program_data);
+    computer.set_profile(profile);
+    computer.set_execution_mode(mode);
+    computer.set_overflow_policy(overflow);
+    computer.set_history(history, Granularity::Instruction);
+    if let Some(microcode) = microcode {
+        computer.set_microcode(microcode);
+    }
+    let script = match input.map(String::as_str) {
+        None => None,
+        Some(path) => {
+            let script = if path == "-" {
+                ScriptedInput::from_reader(io::stdin().lock(), input_format)
+            } else {
+                ScriptedInput::from_file(path, input_format)
+            };
+            match script {
+                Ok(script) => Some(script),
+                Err(e) => {
+                    println!("Failed to read input {path}: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    };
+    let sink = match output.map(String::as_str) {
+        None if rendering == Rendering::default() => None,
+        None | Some("-") => Some(OutputSink::new(io::stdout(), rendering)),
+        Some(path) => match File::create(path) {
+            Ok(file) => Some(OutputSink::new(file, rendering)),
+            Err(e) => {
+                println!("Failed to create {path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let bus = computer.bus_mut();
+    if let Some(device) = teletype_device {
+        let mut teletype = Teletype::terminal();
+        teletype.set_upper_case(teletype_upper);
+        teletype.set_paced(teletype_paced);
+        teletype.set_line_ending(teletype_line_ending);
+        bus.attach(device, teletype);
+    }
+    if let Some(path) = reader {
+        match TapeReader::open(path) {
+            Ok(tape) => {
+                bus.attach(reader_device, tape);
+            }
+            Err(e) => {
+                println!("Failed to read tape {path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if let Some(path) = punch {
+        match TapePunch::append(path) {
+            Ok(tape) => {
+                bus.attach(punch_device, tape);
+            }
+            Err(e) => {
+                println!("Failed to open punch {path}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    match (script, sink) {
+        (Some(script), Some(sink)) if input_device == output_device => {
+            bus.attach(input_device, Duplex::new(script, sink));
+        }
+        (script, sink) => {
+            if let Some(script) = script {
+                bus.attach(input_device, script);
+            }
+            if let Some(sink) = sink {
+                bus.attach(output_device, sink);
+            }
+        }
+    }
+    bus.set_end_of_input_policy(end_of_input);
+    bus.set_clock_rate(clock_rate);
+    for (device, latency) in latencies {
+        bus.set_latency(device, Some(latency));
+    }
+    computer.set_register_trace(!quiet);
+    computer.set_explain_mode(explain);
+    let mut debugger = Debugger::new();
+    if input.is_some_and(|path| path == "-") {
+        // Standard input went to INP, so commands come from the terminal; with none,
+        // the console stops once any --commands have run
+        match File::open("/dev/tty") {
+            Ok(terminal) => debugger.set_command_source(BufReader::new(terminal)),
+            Err(_) => debugger.set_command_source(io::empty()),
+        }
+    }
+    let Some(path) = commands else {
+        println!("Copying program to the RAM");
+        computer.load_program(&program.words);
+        println!("Pressed ON");
+        computer.press_on();
+        computer.run_console(&mut debugger);
+        return ExitCode::SUCCESS;
+    };
+    // Run the commands before the first instruction, so they can set breakpoints
+    if let Err(e) = debugger.queue_script(path) {
+        println!("Failed to read {path}: {e}");
+        return ExitCode::FAILURE;
+    }
+    computer.load_program(&program.words);
+    computer.press_on();
+    computer.pause(PauseReason::Console);
+    computer.run_console(&mut debugger);
+    ExitCode::SUCCESS
 }
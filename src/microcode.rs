@@ -0,0 +1,443 @@
+//! # Microcode
+//!
+//! The 8-tick instruction cycle expressed as data. For every opcode a [`Routine`]
+//! lists the register-transfer [`MicroOp`]s performed at each (state, tick), and the
+//! processor runs them through a generic sequencer. The built-in table reproduces
+//! Foster's machine and lives in `microcode.txt`; instructors can load a text file in
+//! the same format to add instructions or change their timing.
+
+use crate::Instruction;
+use std::{
+    error::Error,
+    fmt,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
+
+/// Number of opcodes: 16 primary opcodes plus 15 extended sub-opcodes under NOP
+pub const OPCODE_COUNT: usize = 31;
+
+/// Ticks in one machine cycle
+pub const TICKS: usize = 8;
+
+/// Text of the built-in microcode table
+const BUILTIN_TEXT: &str = include_str!("microcode.txt");
+
+static BUILTIN: LazyLock<Arc<Microcode>> = LazyLock::new(|| {
+    let mut microcode = Microcode::default();
+    microcode
+        .overlay(BUILTIN_TEXT)
+        .expect("built-in microcode is valid");
+    Arc::new(microcode)
+});
+
+/// A single register transfer or control action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MicroOp {
+    /// PC ← PC + 1
+    IncrementPc,
+    /// PC ← 0
+    ClearPc,
+    /// PC ← operand field of IR
+    PcFromIr,
+    /// PC ← MAR
+    PcFromMar,
+    /// MAR ← PC
+    MarFromPc,
+    /// MAR ← operand field of IR
+    MarFromIr,
+    /// MBR ← 0
+    ClearMbr,
+    /// MBR ← RAM\[MAR\]
+    ReadMemory,
+    /// RAM\[MAR\] ← MBR
+    WriteMemory,
+    /// MBR ← A
+    MbrFromA,
+    /// IR ← 0
+    ClearIr,
+    /// IR ← MBR
+    IrFromMbr,
+    /// Z ← 0
+    ClearZ,
+    /// Z ← A
+    ZFromA,
+    /// A ← 0
+    ClearA,
+    /// A ← MBR
+    AFromMbr,
+    /// A ← PC (return address for SRJ)
+    AFromPc,
+    /// A ← SR
+    AFromSr,
+    /// A ← DIL in the high byte
+    AFromDil,
+    /// A ← Z + MBR, updating flags and checking overflow
+    Add,
+    /// A ← Z - MBR, updating flags
+    Subtract,
+    /// Flags ← Z - MBR, leaving A unchanged
+    Compare,
+    /// A ← Z XOR MBR
+    Xor,
+    /// A ← Z AND MBR
+    And,
+    /// A ← Z OR MBR
+    Ior,
+    /// A ← NOT Z
+    Not,
+    /// A ← Z rotated left one bit
+    RotateLeft,
+    /// DSL ← device field of IR
+    DslFromIr,
+    /// DOL ← high byte of A
+    DolFromA,
+    /// Begin an I/O transfer
+    StartTransfer,
+    /// Finish the I/O transfer
+    EndTransfer,
+    /// Enter the EXECUTE state
+    Execute,
+    /// Enter the FETCH state
+    Fetch,
+    /// Stop the processor, entering the [`Halted`](crate::RunState::Halted) run state
+    Halt,
+    /// Allow device interrupts
+    EnableInterrupts,
+    /// Block device interrupts
+    DisableInterrupts,
+    /// PC ← IPC (return from an interrupt)
+    PcFromIpc,
+}
+
+impl MicroOp {
+    /// Every micro-operation
+    pub const ALL: [Self; 37] = [
+        Self::IncrementPc,
+        Self::ClearPc,
+        Self::PcFromIr,
+        Self::PcFromMar,
+        Self::MarFromPc,
+        Self::MarFromIr,
+        Self::ClearMbr,
+        Self::ReadMemory,
+        Self::WriteMemory,
+        Self::MbrFromA,
+        Self::ClearIr,
+        Self::IrFromMbr,
+        Self::ClearZ,
+        Self::ZFromA,
+        Self::ClearA,
+        Self::AFromMbr,
+        Self::AFromPc,
+        Self::AFromSr,
+        Self::AFromDil,
+        Self::Add,
+        Self::Subtract,
+        Self::Compare,
+        Self::Xor,
+        Self::And,
+        Self::Ior,
+        Self::Not,
+        Self::RotateLeft,
+        Self::DslFromIr,
+        Self::DolFromA,
+        Self::StartTransfer,
+        Self::EndTransfer,
+        Self::Execute,
+        Self::Fetch,
+        Self::Halt,
+        Self::EnableInterrupts,
+        Self::DisableInterrupts,
+        Self::PcFromIpc,
+    ];
+
+    /// Register-transfer notation used in microcode files
+    pub const fn notation(self) -> &'static str {
+        match self {
+            Self::IncrementPc => "PC<-PC+1",
+            Self::ClearPc => "PC<-0",
+            Self::PcFromIr => "PC<-IR",
+            Self::PcFromMar => "PC<-MAR",
+            Self::MarFromPc => "MAR<-PC",
+            Self::MarFromIr => "MAR<-IR",
+            Self::ClearMbr => "MBR<-0",
+            Self::ReadMemory => "MBR<-RAM[MAR]",
+            Self::WriteMemory => "RAM[MAR]<-MBR",
+            Self::MbrFromA => "MBR<-A",
+            Self::ClearIr => "IR<-0",
+            Self::IrFromMbr => "IR<-MBR",
+            Self::ClearZ => "Z<-0",
+            Self::ZFromA => "Z<-A",
+            Self::ClearA => "A<-0",
+            Self::AFromMbr => "A<-MBR",
+            Self::AFromPc => "A<-PC",
+            Self::AFromSr => "A<-SR",
+            Self::AFromDil => "A<-DIL",
+            Self::Add => "A<-Z+MBR",
+            Self::Subtract => "A<-Z-MBR",
+            Self::Compare => "FLAGS<-Z-MBR",
+            Self::Xor => "A<-Z^MBR",
+            Self::And => "A<-Z&MBR",
+            Self::Ior => "A<-Z|MBR",
+            Self::Not => "A<-~Z",
+            Self::RotateLeft => "A<-ROL(Z)",
+            Self::DslFromIr => "DSL<-IR",
+            Self::DolFromA => "DOL<-A",
+            Self::StartTransfer => "START",
+            Self::EndTransfer => "END",
+            Self::Execute => "EXECUTE",
+            Self::Fetch => "FETCH",
+            Self::Halt => "HALT",
+            Self::EnableInterrupts => "IE<-1",
+            Self::DisableInterrupts => "IE<-0",
+            Self::PcFromIpc => "PC<-IPC",
+        }
+    }
+}
+
+/// Condition guarding a micro-operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// The accumulator sign bit is set
+    Negative,
+    /// The selected device has completed the transfer
+    Ready,
+    /// No I/O transfer is in progress
+    Idle,
+    /// The zero flag is set
+    Zero,
+    /// The carry flag is set
+    Carry,
+    /// The overflow flag is set
+    Overflow,
+}
+
+impl Condition {
+    /// Every condition
+    pub const ALL: [Self; 6] = [
+        Self::Negative,
+        Self::Ready,
+        Self::Idle,
+        Self::Zero,
+        Self::Carry,
+        Self::Overflow,
+    ];
+
+    /// Notation used in microcode files, inside `[...]`
+    pub const fn notation(self) -> &'static str {
+        match self {
+            Self::Negative => "A<0",
+            Self::Ready => "READY",
+            Self::Idle => "IDLE",
+            Self::Zero => "ZERO",
+            Self::Carry => "CARRY",
+            Self::Overflow => "OVERFLOW",
+        }
+    }
+}
+
+/// A micro-operation with an optional guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MicroStep {
+    /// Only perform `op` when this holds
+    pub condition: Option<Condition>,
+    /// The operation
+    pub op: MicroOp,
+}
+
+impl fmt::Display for MicroStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(condition) = self.condition {
+            write!(f, "[{}] ", condition.notation())?;
+        }
+        f.write_str(self.op.notation())
+    }
+}
+
+impl FromStr for MicroStep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text: String = s
+            .split_whitespace()
+            .collect::<String>()
+            .to_ascii_uppercase();
+        let (condition, op) = match text.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((condition, op)) => {
+                let condition = Condition::ALL
+                    .into_iter()
+                    .find(|c| c.notation() == condition)
+                    .ok_or_else(|| format!("unknown condition '{condition}'"))?;
+                (Some(condition), op)
+            }
+            None => (None, text.as_str()),
+        };
+        let op = MicroOp::ALL
+            .into_iter()
+            .find(|o| o.notation() == op)
+            .ok_or_else(|| format!("unknown micro-operation '{}'", s.trim()))?;
+        Ok(Self { condition, op })
+    }
+}
+
+/// The micro-operations of one instruction, by state and tick
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Routine {
+    /// Operations during the FETCH cycle
+    pub fetch: [Vec<MicroStep>; TICKS],
+    /// Operations during the EXECUTE cycle
+    pub execute: [Vec<MicroStep>; TICKS],
+}
+
+/// An error in a microcode file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MicrocodeError {
+    /// Line number (1-based)
+    pub line: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for MicrocodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for MicrocodeError {}
+
+/// A complete microcode table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Microcode {
+    /// Sequence shared by every instruction, run first
+    common: Routine,
+    /// Routines indexed by opcode number; `None` marks an undefined opcode
+    routines: [Option<Routine>; OPCODE_COUNT],
+}
+
+impl Microcode {
+    /// The table reproducing Foster's machine and its extensions
+    pub fn builtin() -> Arc<Self> {
+        Arc::clone(&BUILTIN)
+    }
+
+    /// Sequence shared by every instruction
+    pub const fn common(&self) -> &Routine {
+        &self.common
+    }
+
+    /// Routine for `opcode`, `None` if the opcode is undefined
+    pub fn routine(&self, opcode: u8) -> Option<&Routine> {
+        self.routines.get(usize::from(opcode))?.as_ref()
+    }
+
+    /// Apply a microcode file on top of this table
+    ///
+    /// Every opcode mentioned in `text` has its whole routine replaced; the others
+    /// are left alone.
+    ///
+    /// # Errors
+    /// Returns the first malformed line.
+    pub fn overlay(&mut self, text: &str) -> Result<(), MicrocodeError> {
+        let mut replaced = [false; OPCODE_COUNT + 1];
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: String| MicrocodeError {
+                line: index + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let Some(opcode) = fields.next() else {
+                continue;
+            };
+            let (Some(state), Some(tick)) = (fields.next(), fields.next()) else {
+                return Err(error(
+                    "expected '<opcode> <FETCH|EXECUTE> <tick> <ops>'".into(),
+                ));
+            };
+
+            let slot = parse_opcode(opcode).map_err(error)?;
+            let tick = tick
+                .parse::<usize>()
+                .ok()
+                .filter(|&tick| tick < TICKS)
+                .ok_or_else(|| error(format!("tick '{tick}' is not 0-7")))?;
+            let ops: Vec<&str> = fields.collect();
+            let steps = ops
+                .join(" ")
+                .split(',')
+                .filter(|op| !op.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<MicroStep>, _>>()
+                .map_err(error)?;
+
+            let index = slot.map_or(OPCODE_COUNT, usize::from);
+            let routine = match slot {
+                None => &mut self.common,
+                Some(opcode) => self.routines[usize::from(opcode)].get_or_insert_default(),
+            };
+            if !replaced[index] {
+                *routine = Routine::default();
+                replaced[index] = true;
+            }
+            let cells = match state.to_ascii_uppercase().as_str() {
+                "FETCH" => &mut routine.fetch,
+                "EXECUTE" => &mut routine.execute,
+                _ => return Err(error(format!("state '{state}' is not FETCH or EXECUTE"))),
+            };
+            cells[tick].extend(steps);
+        }
+        Ok(())
+    }
+}
+
+/// Resolve `*`, a mnemonic or an opcode number; `None` stands for the common sequence
+pub(crate) fn parse_opcode(text: &str) -> Result<Option<u8>, String> {
+    if text == "*" {
+        return Ok(None);
+    }
+    if let Some(instruction) = Instruction::from_mnemonic(text) {
+        return Ok(Some(instruction.opcode()));
+    }
+    text.parse::<u8>()
+        .ok()
+        .filter(|&opcode| usize::from(opcode) < OPCODE_COUNT)
+        .map(Some)
+        .ok_or_else(|| format!("unknown opcode '{text}'"))
+}
+
+/// Name for an opcode number in listings
+pub(crate) fn opcode_name(opcode: usize) -> String {
+    Instruction::ALL
+        .get(opcode)
+        .map_or_else(|| opcode.to_string(), |i| i.mnemonic().to_string())
+}
+
+impl fmt::Display for Microcode {
+    /// Render in the microcode file format
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routines = std::iter::once(("*".to_string(), Some(&self.common))).chain(
+            self.routines
+                .iter()
+                .enumerate()
+                .map(|(opcode, routine)| (opcode_name(opcode), routine.as_ref())),
+        );
+        for (name, routine) in routines {
+            let Some(routine) = routine else {
+                continue;
+            };
+            for (state, cells) in [("FETCH", &routine.fetch), ("EXECUTE", &routine.execute)] {
+                for (tick, steps) in cells.iter().enumerate() {
+                    if steps.is_empty() {
+                        continue;
+                    }
+                    let steps: Vec<String> = steps.iter().map(ToString::to_string).collect();
+                    writeln!(f, "{name:<4} {state:<8} {tick}  {}", steps.join(", "))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! # Captured Output
+//!
+//! [`OutputSink`] is an output device that either records every byte sent by OUT
+//! or renders them to a writer such as standard output or a file. Tests read the
+//! captured bytes back with [`OutputSink::bytes`]; the command line uses a
+//! [`Rendering`] so programs that print characters produce readable text.
+//!
+//! ```
+//! use blue::{BlueComputer, StopReason, output::OutputSink};
+//!
+//! let mut computer = BlueComputer::new();
+//! computer.bus_mut().attach(2, OutputSink::capture());
+//! computer.load_program(&[0x6003, 0xC002, 0x0000, 0x4F00]); // LDA, OUT 2, HLT, 'O'
+//! computer.press_on();
+//! assert_eq!(computer.run_until(100), StopReason::Halted);
+//! assert_eq!(computer.bus().device::<OutputSink>(2).unwrap().bytes(), b"O");
+//! ```
+
+use crate::device::IoDevice;
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// How output bytes are shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rendering {
+    /// The bytes themselves
+    Raw,
+    /// Printable ASCII, newlines and tabs as they are; anything else as `\xNN`
+    Ascii,
+    /// Two hex digits per byte, one byte per line
+    #[default]
+    Hex,
+    /// The byte as an unsigned decimal number, one per line
+    Decimal,
+}
+
+impl Rendering {
+    /// Every rendering
+    pub const ALL: [Self; 4] = [Self::Raw, Self::Ascii, Self::Hex, Self::Decimal];
+
+    /// Name used on the command line
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Ascii => "ascii",
+            Self::Hex => "hex",
+            Self::Decimal => "decimal",
+        }
+    }
+
+    /// Append the rendering of `byte` to `out`
+    pub fn render(self, byte: u8, out: &mut Vec<u8>) {
+        match self {
+            Self::Raw => out.push(byte),
+            Self::Ascii => match byte {
+                b'\n' | b'\r' | b'\t' | b' '..=b'~' => out.push(byte),
+                _ => out.extend(format!("\\x{byte:02x}").bytes()),
+            },
+            Self::Hex => out.extend(format!("{byte:02x}\n").bytes()),
+            Self::Decimal => out.extend(format!("{byte}\n").bytes()),
+        }
+    }
+}
+
+impl fmt::Display for Rendering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Rendering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|rendering| rendering.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown output format '{s}' (raw, ascii, hex, decimal)"))
+    }
+}
+
+/// An output device that records OUT bytes or renders them to a writer
+pub struct OutputSink {
+    bytes: Vec<u8>,
+    writer: Option<Box<dyn Write>>,
+    rendering: Rendering,
+}
+
+impl OutputSink {
+    /// Record bytes without writing them anywhere
+    pub const fn capture() -> Self {
+        Self {
+            bytes: Vec::new(),
+            writer: None,
+            rendering: Rendering::Raw,
+        }
+    }
+
+    /// Write each byte to `writer` as `rendering`, without recording it
+    pub fn new(writer: impl Write + 'static, rendering: Rendering) -> Self {
+        Self {
+            bytes: Vec::new(),
+            writer: Some(Box::new(writer)),
+            rendering,
+        }
+    }
+
+    /// Every byte received so far by a [`capture`](Self::capture) sink
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Take the bytes received so far, leaving the record empty
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+
+    /// The bytes received so far as `rendering` would write them
+    pub fn rendered(&self, rendering: Rendering) -> Vec<u8> {
+        let mut out = Vec::new();
+        for &byte in &self.bytes {
+            rendering.render(byte, &mut out);
+        }
+        out
+    }
+}
+
+impl fmt::Debug for OutputSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutputSink")
+            .field("bytes", &self.bytes)
+            .field("rendering", &self.rendering)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IoDevice for OutputSink {
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        let Some(writer) = &mut self.writer else {
+            self.bytes.push(byte);
+            return Ok(());
+        };
+        let mut out = Vec::new();
+        self.rendering.render(byte, &mut out);
+        writer.write_all(&out)?;
+        writer.flush()
+    }
+
+    fn handles_input(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,614 @@
+//! # Machine Snapshots
+//!
+//! A [`Snapshot`] captures everything needed to resume a machine exactly where it
+//! stopped: registers, flags, RAM, the FETCH/EXECUTE state and clock pulse, the run
+//! state, the I/O handshake, the interrupt enable and breakpoints. Attached devices
+//! and the microcode table are not included.
+//!
+//! Snapshots are stored either as a compact binary image starting with `BLUE` or as
+//! a line-oriented text file starting with `blue-snapshot`, so a paused machine can be
+//! read, diffed and edited by hand. Both carry a format version.
+
+use crate::{
+    BlueRegister, Fault, FaultKind, HaltReason, IsaProfile, MicroTick, PauseReason, RAM_LENGTH,
+    RunState, blue::Registers, breakpoint::WatchHit,
+};
+use std::{error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+/// Format version written by this build
+pub const VERSION: u16 = 3;
+
+/// First bytes of a binary snapshot
+const MAGIC: &[u8; 4] = b"BLUE";
+
+/// First word of a text snapshot
+const TEXT_HEADER: &str = "blue-snapshot";
+
+/// Words per `ram` line in the text format
+const TEXT_ROW: usize = 8;
+
+/// Longest I/O fault message saved, in bytes; longer ones are cut short
+const MESSAGE_LIMIT: usize = 1024;
+
+/// Why a snapshot could not be read
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The file could not be read or written
+    Io(io::Error),
+    /// The snapshot was written by a newer, unknown format version
+    UnsupportedVersion(u16),
+    /// The contents are not a valid snapshot
+    Malformed(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "unsupported snapshot version {version} (expected 1-{VERSION})"
+                )
+            }
+            Self::Malformed(message) => write!(f, "malformed snapshot: {message}"),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The complete architectural state of a Blue computer
+///
+/// Taken with [`BlueComputer::save_state`](crate::BlueComputer::save_state) and
+/// restored with [`BlueComputer::load_state`](crate::BlueComputer::load_state).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub(crate) profile: IsaProfile,
+    pub(crate) registers: Registers,
+    pub(crate) run_state: RunState,
+    pub(crate) breakpoints: Vec<BlueRegister>,
+    pub(crate) ram: Box<[u16; RAM_LENGTH]>,
+}
+
+impl Snapshot {
+    /// Registers in the order they are stored, with their text names
+    fn registers(&self) -> [(&'static str, BlueRegister); 12] {
+        [
+            ("pc", self.registers.pc),
+            ("a", self.registers.a),
+            ("z", self.registers.z),
+            ("sr", self.registers.sr),
+            ("mar", self.registers.mar),
+            ("mbr", self.registers.mbr),
+            ("ir", self.registers.ir),
+            ("dsl", self.registers.dsl),
+            ("dil", self.registers.dil),
+            ("dol", self.registers.dol),
+            ("flags", self.registers.flags),
+            ("ipc", self.registers.ipc),
+        ]
+    }
+
+    /// Mutable access to a register by its text name
+    fn register_mut(&mut self, name: &str) -> Option<&mut BlueRegister> {
+        Some(match name {
+            "pc" => &mut self.registers.pc,
+            "a" => &mut self.registers.a,
+            "z" => &mut self.registers.z,
+            "sr" => &mut self.registers.sr,
+            "mar" => &mut self.registers.mar,
+            "mbr" => &mut self.registers.mbr,
+            "ir" => &mut self.registers.ir,
+            "dsl" => &mut self.registers.dsl,
+            "dil" => &mut self.registers.dil,
+            "dol" => &mut self.registers.dol,
+            "flags" => &mut self.registers.flags,
+            "ipc" => &mut self.registers.ipc,
+            _ => return None,
+        })
+    }
+
+    /// A powered-off machine with everything zeroed
+    fn empty() -> Self {
+        Self {
+            profile: IsaProfile::default(),
+            registers: Registers::default(),
+            run_state: RunState::PoweredOff,
+            breakpoints: Vec::new(),
+            ram: Box::new([0; RAM_LENGTH]),
+        }
+    }
+
+    /// Encode as a binary snapshot
+    ///
+    /// Layout (little-endian): `BLUE`, version, profile, state bits, clock pulse,
+    /// twelve registers, breakpoint count and addresses, all of RAM, then the run
+    /// state as the byte length and UTF-8 of its text form, padded to a whole word.
+    /// Version 1 had no IPC register and versions before 3 no run state.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + 2 * (self.breakpoints.len() + RAM_LENGTH));
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(
+            IsaProfile::ALL
+                .iter()
+                .position(|&p| p == self.profile)
+                .unwrap_or(0) as u8,
+        );
+        bytes.push(
+            u8::from(self.registers.execute)
+                | u8::from(self.registers.power) << 1
+                | u8::from(self.registers.transfer_active) << 2
+                | u8::from(self.registers.ready) << 3
+                | u8::from(self.registers.interrupts) << 4,
+        );
+        bytes.push(self.registers.clock_pulse);
+        let breakpoints = u16::try_from(self.breakpoints.len()).unwrap_or(u16::MAX);
+        let run_state = format_run_state(&self.run_state);
+        let words = self
+            .registers()
+            .into_iter()
+            .map(|(_, value)| value)
+            .chain([breakpoints])
+            .chain(
+                self.breakpoints
+                    .iter()
+                    .copied()
+                    .take(usize::from(breakpoints)),
+            )
+            .chain(self.ram.iter().copied())
+            .chain(string_words(&run_state));
+        for word in words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decode a binary snapshot
+    ///
+    /// # Errors
+    /// Fails on a missing header, an unknown version or truncated contents.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let truncated = || SnapshotError::Malformed("truncated snapshot".into());
+        let rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| SnapshotError::Malformed("missing BLUE header".into()))?;
+        let (&[v0, v1, profile, bits, clock_pulse], rest) =
+            rest.split_first_chunk::<5>().ok_or_else(truncated)?;
+        let version = u16::from_le_bytes([v0, v1]);
+        if !(1..=VERSION).contains(&version) {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut words = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+        let mut next = || words.next().ok_or_else(truncated);
+
+        let mut snapshot = Self::empty();
+        snapshot.profile = *IsaProfile::ALL
+            .get(usize::from(profile))
+            .ok_or_else(|| SnapshotError::Malformed(format!("unknown profile {profile}")))?;
+        snapshot.registers.execute = bits & 1 != 0;
+        snapshot.registers.power = bits & 2 != 0;
+        snapshot.registers.transfer_active = bits & 4 != 0;
+        snapshot.registers.ready = bits & 8 != 0;
+        snapshot.registers.interrupts = bits & 16 != 0;
+        snapshot.registers.clock_pulse = clock_pulse;
+        let stored = if version == 1 { 11 } else { 12 };
+        for (name, _) in snapshot.registers().into_iter().take(stored) {
+            let value = next()?;
+            *snapshot.register_mut(name).unwrap() = value;
+        }
+        for _ in 0..next()? {
+            let addr = next()?;
+            snapshot.breakpoints.push(addr);
+        }
+        for word in snapshot.ram.iter_mut() {
+            *word = next()?;
+        }
+        if version >= 3 {
+            let length = usize::from(next()?);
+            let mut text = Vec::with_capacity(length + 1);
+            for _ in 0..length.div_ceil(2) {
+                text.extend_from_slice(&next()?.to_le_bytes());
+            }
+            text.truncate(length);
+            let text = String::from_utf8(text)
+                .map_err(|_| SnapshotError::Malformed("run state is not UTF-8".into()))?;
+            snapshot.run_state = parse_run_state(&text).map_err(SnapshotError::Malformed)?;
+        }
+        snapshot.settle_run_state(version);
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    /// Derive the run state of versions before 3 from the power bit, which now
+    /// follows the run state
+    ///
+    /// Those versions only recorded whether the clock was running, so a running or
+    /// paused machine comes back paused and any other powered off.
+    fn settle_run_state(&mut self, version: u16) {
+        if version < 3 {
+            self.run_state = if self.registers.power {
+                RunState::Paused(PauseReason::Console)
+            } else {
+                RunState::PoweredOff
+            };
+        }
+        self.registers.power = matches!(self.run_state, RunState::Running | RunState::Paused(_));
+    }
+
+    /// Reject states the processor can never be in
+    fn validate(&self) -> Result<(), SnapshotError> {
+        if usize::from(self.registers.clock_pulse) >= 8 {
+            return Err(SnapshotError::Malformed(format!(
+                "clock pulse {} is not 0-7",
+                self.registers.clock_pulse
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write to `path`: text if the extension is `txt`, binary otherwise
+    ///
+    /// # Errors
+    /// Returns any error from writing the file.
+    pub fn write_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|ext| ext == "txt") {
+            fs::write(path, self.to_string())
+        } else {
+            fs::write(path, self.to_bytes())
+        }
+    }
+
+    /// Read a snapshot from `path`, in either format
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid snapshot.
+    pub fn read_file(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(MAGIC) {
+            return Self::from_bytes(&bytes);
+        }
+        String::from_utf8(bytes)
+            .map_err(|_| SnapshotError::Malformed("neither binary nor text".into()))?
+            .parse()
+    }
+}
+
+impl fmt::Display for Snapshot {
+    /// Render in the text format; RAM rows that are all zero are left out
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{TEXT_HEADER} {VERSION}")?;
+        writeln!(f, "profile {}", self.profile)?;
+        writeln!(
+            f,
+            "state {}",
+            if self.registers.execute {
+                "execute"
+            } else {
+                "fetch"
+            }
+        )?;
+        writeln!(f, "tick {}", self.registers.clock_pulse)?;
+        writeln!(f, "run {}", format_run_state(&self.run_state))?;
+        writeln!(
+            f,
+            "transfer {}",
+            if self.registers.transfer_active {
+                "active"
+            } else {
+                "idle"
+            }
+        )?;
+        writeln!(
+            f,
+            "ready {}",
+            if self.registers.ready { "yes" } else { "no" }
+        )?;
+        writeln!(
+            f,
+            "interrupts {}",
+            if self.registers.interrupts {
+                "on"
+            } else {
+                "off"
+            }
+        )?;
+        for (name, value) in self.registers() {
+            writeln!(f, "{name} {value:04x}")?;
+        }
+        write!(f, "breakpoints")?;
+        for addr in &self.breakpoints {
+            write!(f, " {addr:03x}")?;
+        }
+        writeln!(f)?;
+        for (row, words) in self.ram.chunks(TEXT_ROW).enumerate() {
+            if words.iter().all(|&word| word == 0) {
+                continue;
+            }
+            write!(f, "ram {:03x}:", row * TEXT_ROW)?;
+            for word in words {
+                write!(f, " {word:04x}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = SnapshotError;
+
+    /// Parse the text format; missing RAM rows are zero
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, strip_comment(line)))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, header) = lines
+            .next()
+            .ok_or_else(|| SnapshotError::Malformed("empty snapshot".into()))?;
+        let version = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [TEXT_HEADER, version] => version.parse::<u16>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| SnapshotError::Malformed(format!("missing '{TEXT_HEADER}' header")))?;
+        if !(1..=VERSION).contains(&version) {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut snapshot = Self::empty();
+        for (number, line) in lines {
+            let malformed =
+                |message: String| SnapshotError::Malformed(format!("line {number}: {message}"));
+            let hex = |text: &str| {
+                u16::from_str_radix(text, 16).map_err(|_| malformed(format!("bad number '{text}'")))
+            };
+            let (key, value) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let value = value.trim();
+            match key {
+                "profile" => snapshot.profile = value.parse().map_err(malformed)?,
+                "state" => {
+                    snapshot.registers.execute = match value {
+                        "fetch" => false,
+                        "execute" => true,
+                        _ => return Err(malformed(format!("unknown state '{value}'"))),
+                    }
+                }
+                "tick" => {
+                    snapshot.registers.clock_pulse = value
+                        .parse()
+                        .map_err(|_| malformed(format!("bad tick '{value}'")))?;
+                }
+                "power" => snapshot.registers.power = value == "on",
+                "run" => snapshot.run_state = parse_run_state(value).map_err(malformed)?,
+                "transfer" => snapshot.registers.transfer_active = value == "active",
+                "ready" => snapshot.registers.ready = value == "yes",
+                "interrupts" => snapshot.registers.interrupts = value == "on",
+                "breakpoints" => {
+                    snapshot.breakpoints = value
+                        .split_whitespace()
+                        .map(hex)
+                        .collect::<Result<_, _>>()?;
+                }
+                "ram" => {
+                    let (start, words) = value
+                        .split_once(':')
+                        .ok_or_else(|| malformed("expected 'ram <addr>: <words>'".into()))?;
+                    let start = usize::from(hex(start.trim())?);
+                    for (offset, word) in words.split_whitespace().enumerate() {
+                        let slot = snapshot
+                            .ram
+                            .get_mut(start + offset)
+                            .ok_or_else(|| malformed("address out of range".into()))?;
+                        *slot = hex(word)?;
+                    }
+                }
+                name => {
+                    let value = hex(value)?;
+                    *snapshot
+                        .register_mut(name)
+                        .ok_or_else(|| malformed(format!("unknown field '{name}'")))? = value;
+                }
+            }
+        }
+        snapshot.settle_run_state(version);
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+}
+
+/// `line` up to any `#` comment; a `#` inside a quoted string is kept
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Undo the quoting and escapes of a string written with `{:?}`
+fn unquote(text: &str) -> Option<String> {
+    let mut chars = text.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut unquoted = String::new();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        unquoted.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                let (code, rest) = chars.as_str().strip_prefix('{')?.split_once('}')?;
+                chars = rest.chars();
+                char::from_u32(u32::from_str_radix(code, 16).ok()?)?
+            }
+            c @ ('\\' | '"' | '\'') => c,
+            _ => return None,
+        });
+    }
+    Some(unquoted)
+}
+
+/// At most the first `limit` bytes of `text`, cut at a character boundary
+fn truncate(text: &str, limit: usize) -> &str {
+    let mut end = text.len().min(limit);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// `text` as its byte length followed by its bytes, two to a word
+fn string_words(text: &str) -> impl Iterator<Item = u16> + '_ {
+    let bytes = truncate(text, usize::from(u16::MAX)).as_bytes();
+    let length = u16::try_from(bytes.len()).unwrap_or(u16::MAX);
+    [length].into_iter().chain(
+        bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair.get(1).copied().unwrap_or(0)])),
+    )
+}
+
+/// Render a run state as the value of the text format's `run` line, e.g. `halted hlt`
+/// or `faulted 0005 f900 opcode f900`; I/O messages are quoted and escaped
+fn format_run_state(run_state: &RunState) -> String {
+    match run_state {
+        RunState::PoweredOff => "off".into(),
+        // Saving stops the clock, as a running machine comes back paused
+        RunState::Running | RunState::Paused(PauseReason::Console) => "paused console".into(),
+        RunState::Paused(PauseReason::Step) => "paused step".into(),
+        RunState::Paused(PauseReason::Breakpoint(addr)) => format!("paused breakpoint {addr:03x}"),
+        RunState::Paused(PauseReason::Watchpoint(hit)) => format!(
+            "paused watchpoint {} {:03x} {:03x} {} {:04x} {:04x}",
+            hit.id,
+            hit.instruction,
+            hit.address,
+            if hit.write { "write" } else { "read" },
+            hit.old,
+            hit.new
+        ),
+        RunState::Paused(PauseReason::TickBreakpoint(tick)) => format!(
+            "paused tick {:02x} {} {}",
+            tick.opcode, tick.state, tick.tick
+        ),
+        RunState::Halted(reason) => format!(
+            "halted {}",
+            match reason {
+                HaltReason::Instruction => "hlt",
+                HaltReason::Overflow => "overflow",
+                HaltReason::EndOfInput => "eof",
+            }
+        ),
+        RunState::Faulted(fault) => {
+            let kind = match &fault.kind {
+                FaultKind::InvalidOpcode(word) => format!("opcode {word:04x}"),
+                FaultKind::AddressOutOfRange(addr) => format!("address {addr:04x}"),
+                FaultKind::Io(message) => format!("io {:?}", truncate(message, MESSAGE_LIMIT)),
+                FaultKind::UnattachedDevice(device) => format!("unattached {device:02x}"),
+                FaultKind::EndOfInput(device) => format!("eof {device:02x}"),
+                FaultKind::Overflow => "overflow".into(),
+            };
+            format!("faulted {:04x} {:04x} {kind}", fault.pc, fault.ir)
+        }
+    }
+}
+
+/// Parse what [`format_run_state`] writes
+fn parse_run_state(text: &str) -> Result<RunState, String> {
+    let hex =
+        |text: &str| u16::from_str_radix(text, 16).map_err(|_| format!("bad number '{text}'"));
+    let byte =
+        |text: &str| u8::from_str_radix(text, 16).map_err(|_| format!("bad number '{text}'"));
+    let unknown = || format!("unknown run state '{text}'");
+    let words: Vec<_> = text.split_whitespace().collect();
+    Ok(match words[..] {
+        ["off"] => RunState::PoweredOff,
+        ["paused", "console"] => RunState::Paused(PauseReason::Console),
+        ["paused", "step"] => RunState::Paused(PauseReason::Step),
+        ["paused", "breakpoint", addr] => RunState::Paused(PauseReason::Breakpoint(hex(addr)?)),
+        [
+            "paused",
+            "watchpoint",
+            id,
+            instruction,
+            address,
+            access,
+            old,
+            new,
+        ] => RunState::Paused(PauseReason::Watchpoint(WatchHit {
+            id: id
+                .parse()
+                .map_err(|_| format!("bad watchpoint id '{id}'"))?,
+            instruction: hex(instruction)?,
+            address: hex(address)?,
+            write: match access {
+                "read" => false,
+                "write" => true,
+                _ => return Err(unknown()),
+            },
+            old: hex(old)?,
+            new: hex(new)?,
+        })),
+        ["paused", "tick", opcode, state, tick] => {
+            RunState::Paused(PauseReason::TickBreakpoint(MicroTick {
+                opcode: byte(opcode)?,
+                state: state.parse()?,
+                tick: tick
+                    .parse()
+                    .ok()
+                    .filter(|&tick| tick < 8)
+                    .ok_or_else(|| format!("tick '{tick}' is not 0-7"))?,
+            }))
+        }
+        ["halted", reason] => RunState::Halted(match reason {
+            "hlt" => HaltReason::Instruction,
+            "overflow" => HaltReason::Overflow,
+            "eof" => HaltReason::EndOfInput,
+            _ => return Err(unknown()),
+        }),
+        ["faulted", pc, ir, kind, ref rest @ ..] => {
+            let kind = match (kind, rest) {
+                ("opcode", [word]) => FaultKind::InvalidOpcode(hex(word)?),
+                ("address", [addr]) => FaultKind::AddressOutOfRange(hex(addr)?),
+                // The message is the rest of the line, quoted and escaped
+                ("io", [_, ..]) => {
+                    let message = text.trim_start().splitn(5, ' ').nth(4).unwrap_or_default();
+                    FaultKind::Io(unquote(message).ok_or_else(|| format!("bad message {message}"))?)
+                }
+                ("unattached", [device]) => FaultKind::UnattachedDevice(byte(device)?),
+                ("eof", [device]) => FaultKind::EndOfInput(byte(device)?),
+                ("overflow", []) => FaultKind::Overflow,
+                _ => return Err(unknown()),
+            };
+            RunState::Faulted(Fault {
+                kind,
+                pc: hex(pc)?,
+                ir: hex(ir)?,
+            })
+        }
+        _ => return Err(unknown()),
+    })
+}
@@ -0,0 +1,132 @@
+//! # Paper Tape
+//!
+//! [`TapeReader`] feeds INP from a file one frame (byte) at a time and reports the
+//! end of the tape, so the bus applies its
+//! [`EndOfInputPolicy`](crate::device::EndOfInputPolicy) when a program reads past
+//! it. [`TapePunch`] appends every OUT byte to a file.
+//!
+//! ```no_run
+//! use blue::{BlueComputer, tape::{TapePunch, TapeReader}};
+//!
+//! let mut computer = BlueComputer::new();
+//! computer.bus_mut().attach(1, TapeReader::open("data.tape")?);
+//! computer.bus_mut().attach(2, TapePunch::append("out.tape")?);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::device::IoDevice;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+/// A paper tape reader loaded with a tape
+#[derive(Debug, Clone, Default)]
+pub struct TapeReader {
+    tape: Vec<u8>,
+    position: usize,
+}
+
+impl TapeReader {
+    /// Load a tape holding `bytes`
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            tape: bytes.into(),
+            position: 0,
+        }
+    }
+
+    /// Load the contents of the file at `path` as a tape
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(fs::read(path)?))
+    }
+
+    /// Number of frames read so far
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of frames on the tape
+    pub const fn len(&self) -> usize {
+        self.tape.len()
+    }
+
+    /// Whether the tape has no frames at all
+    pub const fn is_empty(&self) -> bool {
+        self.tape.is_empty()
+    }
+
+    /// Wind the tape back to its first frame
+    pub const fn rewind(&mut self) {
+        self.position = 0;
+    }
+}
+
+impl IoDevice for TapeReader {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let byte = *self
+            .tape
+            .get(self.position)
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn handles_output(&self) -> bool {
+        false
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.tape.len()
+    }
+}
+
+/// A paper tape punch writing to a file
+#[derive(Debug)]
+pub struct TapePunch {
+    file: File,
+    punched: usize,
+}
+
+impl TapePunch {
+    /// Punch onto the end of the file at `path`, creating it if needed
+    ///
+    /// # Errors
+    /// Fails if the file cannot be opened for appending.
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, punched: 0 })
+    }
+
+    /// Punch a fresh tape at `path`, replacing any existing file
+    ///
+    /// # Errors
+    /// Fails if the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            file: File::create(path)?,
+            punched: 0,
+        })
+    }
+
+    /// Number of frames punched since the device was attached
+    pub const fn punched(&self) -> usize {
+        self.punched
+    }
+}
+
+impl IoDevice for TapePunch {
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.file.write_all(&[byte])?;
+        self.punched += 1;
+        Ok(())
+    }
+
+    fn handles_input(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,159 @@
+//! # Teletype
+//!
+//! [`Teletype`] emulates the ASCII teletype Foster's Blue was meant to drive: OUT
+//! prints a character and INP waits for a keystroke. On the host terminal keys are
+//! read one at a time and without local echo, so a program sees Return as CR and
+//! echoes characters itself, just as it would on the real machine.
+//!
+//! A terminal teletype switches the terminal to character mode once, when it is
+//! created, and restores it when dropped. Ctrl-C reaches INP as a key, and stops
+//! the program with an I/O fault.
+
+use crate::device::{IoDevice, Latency};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+};
+
+/// How line breaks are exchanged with the program
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// CR returns the carriage and LF feeds the paper, as on a real teletype;
+    /// Return is read as CR
+    #[default]
+    Teletype,
+    /// LF alone starts a new line and Return is read as LF, for programs written
+    /// for modern terminals
+    Newline,
+}
+
+/// An ASCII terminal attached to the I/O bus
+pub struct Teletype {
+    keyboard: Box<dyn Read>,
+    printer: Box<dyn Write>,
+    /// Terminal settings to restore on drop, for a teletype on the host terminal
+    saved_terminal: Option<String>,
+    line_ending: LineEnding,
+    upper_case: bool,
+    paced: bool,
+}
+
+impl Teletype {
+    /// Print rate of a Model 33 teletype
+    pub const CHARACTERS_PER_SECOND: u32 = 10;
+
+    /// A teletype on the host terminal, reading keys from standard input
+    ///
+    /// Puts the terminal in character mode until the teletype is dropped; if
+    /// standard input is not a terminal nothing is changed.
+    pub fn terminal() -> Self {
+        let mut teletype = Self::new(io::stdin(), io::stdout());
+        teletype.saved_terminal = stty(&["-g"]).ok();
+        if teletype.saved_terminal.is_some() {
+            let _ = stty(&["-icanon", "-echo", "-isig", "min", "1", "time", "0"]);
+        }
+        teletype
+    }
+
+    /// A teletype reading keys from `keyboard` and printing to `printer`
+    pub fn new(keyboard: impl Read + 'static, printer: impl Write + 'static) -> Self {
+        Self {
+            keyboard: Box::new(keyboard),
+            printer: Box::new(printer),
+            saved_terminal: None,
+            line_ending: LineEnding::default(),
+            upper_case: false,
+            paced: false,
+        }
+    }
+
+    /// Choose how line breaks are exchanged
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Restrict both directions to 7-bit upper-case ASCII, like a Model 33
+    pub fn set_upper_case(&mut self, upper_case: bool) {
+        self.upper_case = upper_case;
+    }
+
+    /// Hold each OUT for one character time so output runs at
+    /// [`CHARACTERS_PER_SECOND`](Self::CHARACTERS_PER_SECOND) of emulated time
+    pub fn set_paced(&mut self, paced: bool) {
+        self.paced = paced;
+    }
+
+    /// Apply the upper-case/7-bit restriction to a character
+    const fn restrict(&self, byte: u8) -> u8 {
+        if self.upper_case {
+            (byte & 0x7F).to_ascii_uppercase()
+        } else {
+            byte
+        }
+    }
+}
+
+impl fmt::Debug for Teletype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Teletype")
+            .field("terminal", &self.saved_terminal.is_some())
+            .field("line_ending", &self.line_ending)
+            .field("upper_case", &self.upper_case)
+            .field("paced", &self.paced)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IoDevice for Teletype {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let terminal = self.saved_terminal.is_some();
+        let mut key = [0];
+        self.keyboard.read_exact(&mut key)?;
+        if terminal && key[0] == 0x03 {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "break"));
+        }
+        Ok(match (self.restrict(key[0]), self.line_ending) {
+            (b'\r', LineEnding::Newline) => b'\n',
+            // The terminal still turns Return into LF, for the console's sake
+            (b'\n', LineEnding::Teletype) if terminal => b'\r',
+            (byte, _) => byte,
+        })
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        match (self.restrict(byte), self.line_ending) {
+            (b'\n', LineEnding::Newline) => self.printer.write_all(b"\r\n")?,
+            (byte, _) => self.printer.write_all(&[byte])?,
+        }
+        self.printer.flush()
+    }
+
+    fn latency(&self, input: bool) -> Latency {
+        if self.paced && !input {
+            Latency::Micros(1_000_000 / u64::from(Self::CHARACTERS_PER_SECOND))
+        } else {
+            Latency::Immediate
+        }
+    }
+}
+
+impl Drop for Teletype {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.saved_terminal {
+            let _ = stty(&[saved.trim()]);
+        }
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,122 @@
+use blue::asm::{self, AsmError};
+
+/// Assemble `source` and return its messages, as `line:column: message`
+fn errors(source: &str) -> Vec<String> {
+    asm::assemble(source)
+        .unwrap_err()
+        .iter()
+        .map(AsmError::to_string)
+        .collect()
+}
+
+#[test]
+fn labels_resolve_forwards_and_backwards() {
+    let assembly = asm::assemble(
+        "
+        start:  LDA x       ; forward reference
+                JMA done
+                JMP start
+        done:
+                HLT
+        x:      DW 5
+        ",
+    )
+    .unwrap();
+    assert_eq!(assembly.words, [0x6004, 0x9003, 0xA000, 0x0000, 0x0005]);
+    assert_eq!(assembly.symbols["start"], 0);
+    assert_eq!(assembly.symbols["done"], 3);
+    assert_eq!(assembly.symbols["x"], 4);
+}
+
+#[test]
+fn org_and_data_place_words() {
+    let assembly = asm::assemble(
+        "
+                JMP main
+                ORG 0x10
+        table:  DATA 1, 2, 3
+        main:   lda table
+                dw -1
+        ",
+    )
+    .unwrap();
+    assert_eq!(assembly.words.len(), 0x15);
+    assert_eq!(assembly.words[0], 0xA013);
+    assert!(assembly.words[1..0x10].iter().all(|&word| word == 0));
+    assert_eq!(assembly.words[0x10..], [1, 2, 3, 0x6010, 0xFFFF]);
+}
+
+#[test]
+fn literals_and_expressions() {
+    let assembly = asm::assemble(
+        "
+        a:      DW 42, 0x2a, 0b10_1010, 1_000, 'A', '\\n', '\\''
+        b:      DW *, * + 1, b - a, a + 2, -'0' + '9'
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        assembly.words,
+        [42, 42, 42, 1000, 0x41, 0x0A, 0x27, 7, 9, 7, 2, 9]
+    );
+}
+
+#[test]
+fn label_mistakes_are_reported_where_they_are() {
+    assert_eq!(
+        errors("x: HLT\nx: DW 1\n        LDA nowhere"),
+        [
+            "2:1: duplicate label 'x'",
+            "3:13: undefined label 'nowhere'"
+        ]
+    );
+}
+
+#[test]
+fn operands_are_checked() {
+    assert_eq!(
+        errors("NOT 5\nHLT 0x123\nLDA\nJMP 0x1000\nSUB 0x100"),
+        [
+            "1:5: NOT takes no operand",
+            "2:5: HLT takes no operand",
+            "3:1: LDA requires an operand",
+            "4:5: operand 4096 out of range for JMP (0-0xfff)",
+            "5:5: SUB only addresses page 0 (0-0xff), not 0x100",
+        ]
+    );
+    assert_eq!(
+        errors("JMZ far\nORG 0x100\nfar: HLT"),
+        ["1:5: JMZ only reaches page 0 (0-0xff), not 0x100; \
+          JMZ to a 'JMP 0x100' placed in page 0 instead"]
+    );
+    assert_eq!(
+        errors("  FOO 1\n  DW 1 2\n  DW 0x10000\n  DW 'ab'"),
+        [
+            "1:3: unknown mnemonic 'FOO'",
+            "2:8: unexpected text after statement",
+            "3:6: value 65536 does not fit in a word",
+            "4:6: invalid character literal",
+        ]
+    );
+}
+
+#[test]
+fn programs_must_fit_in_memory() {
+    assert_eq!(
+        errors("ORG 0xFFF\nDW 1, 2"),
+        ["2:1: program exceeds memory"]
+    );
+    assert_eq!(
+        errors("ORG 0xFFF\nHLT\nend: HLT"),
+        ["3:1: label is past the end of memory"]
+    );
+    assert_eq!(errors("ORG 4096"), ["1:5: ORG address 4096 out of range"]);
+    assert_eq!(
+        errors("HLT\nORG 0\nNOP"),
+        ["3:1: address 0x000 is already assembled"]
+    );
+
+    let assembly = asm::assemble("ORG 0xFFF\nlast: HLT").unwrap();
+    assert_eq!(assembly.words.len(), 4096);
+    assert_eq!(assembly.symbols["last"], 0xFFF);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use blue::{
+    BlueComputer, HaltReason, PauseReason, RegisterName, RunState, StopReason,
+    breakpoint::{Comparison, Condition, Operand},
+    debugger::{Command, Debugger},
+};
+use common::{machine, symbol};
+
+/// Count `n` down to zero, passing `loop` once per value
+const COUNTDOWN: &str = "
+        loop:   LDA n
+                ADD minus
+                STA n
+                JMA done
+                JMP loop
+        done:   HLT
+        n:      DW 5
+        minus:  DW 0xFFFF
+";
+
+/// Value of `n` at each stop until the machine halts
+fn stops(computer: &mut BlueComputer) -> Vec<u16> {
+    let mut values = Vec::new();
+    while let StopReason::Breakpoint(_) = computer.run_until(1_000) {
+        values.push(computer.read_memory(symbol(COUNTDOWN, "n")).unwrap());
+    }
+    values
+}
+
+#[test]
+fn conditions_and_ignore_counts_filter_hits() {
+    let mut computer = machine(COUNTDOWN);
+    let id = computer.add_breakpoint(symbol(COUNTDOWN, "loop"));
+    let condition = format!("[{}] <= 3", symbol(COUNTDOWN, "n"));
+    computer.breakpoints_mut().get_mut(id).unwrap().condition = Some(condition.parse().unwrap());
+    assert_eq!(stops(&mut computer), [3, 2, 1, 0]);
+
+    let mut computer = machine(COUNTDOWN);
+    let id = computer.add_breakpoint(symbol(COUNTDOWN, "loop"));
+    computer.breakpoints_mut().get_mut(id).unwrap().ignore = 2;
+    assert_eq!(stops(&mut computer), [2, 1, 0]);
+    assert_eq!(computer.breakpoints().get(id).unwrap().hits, 5);
+}
+
+#[test]
+fn disabled_temporary_and_deleted_breakpoints() {
+    let mut computer = machine(COUNTDOWN);
+    let id = computer.add_breakpoint(symbol(COUNTDOWN, "loop"));
+    computer.breakpoints_mut().get_mut(id).unwrap().enabled = false;
+    computer
+        .breakpoints_mut()
+        .add_temporary(symbol(COUNTDOWN, "loop"));
+    assert_eq!(stops(&mut computer), [4]);
+    assert_eq!(computer.breakpoints().len(), 1);
+
+    let mut computer = machine(COUNTDOWN);
+    let id = computer.add_breakpoint(symbol(COUNTDOWN, "loop"));
+    assert!(computer.breakpoints_mut().remove(id).is_some());
+    assert!(computer.breakpoints_mut().remove(id).is_none());
+    assert_eq!(stops(&mut computer), []);
+}
+
+#[test]
+fn parse_conditions() {
+    let condition: Condition = "A == 0x8000".parse().unwrap();
+    assert_eq!(
+        condition,
+        Condition {
+            left: Operand::Register(RegisterName::A),
+            comparison: Comparison::Equal,
+            right: Operand::Constant(0x8000),
+        }
+    );
+    assert_eq!(condition.to_string().parse(), Ok(condition));
+
+    let condition: Condition = "[0x20]>=pc".parse().unwrap();
+    assert_eq!(condition.left, Operand::Memory(0x20));
+    assert_eq!(condition.comparison, Comparison::GreaterOrEqual);
+    assert_eq!(condition.right, Operand::Register(RegisterName::Pc));
+
+    for bad in ["A = 1", "A == ", "[0x1000] != 0", "Q < 3"] {
+        assert!(bad.parse::<Condition>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn console_commands_for_breakpoints() {
+    let command: Command = "break 0x20 if A == 0x8000".parse().unwrap();
+    assert_eq!(
+        command,
+        Command::Break {
+            address: 0x20,
+            condition: Some("A == 0x8000".parse().unwrap()),
+            temporary: false,
+        }
+    );
+    assert!(matches!(
+        "tb 5".parse(),
+        Ok(Command::Break {
+            temporary: true,
+            ..
+        })
+    ));
+    assert_eq!(
+        "ignore 2 10".parse(),
+        Ok(Command::Ignore { id: 2, count: 10 })
+    );
+    assert_eq!(
+        "condition 2".parse(),
+        Ok(Command::Condition {
+            id: 2,
+            condition: None
+        })
+    );
+    assert!("break 0x20 when A == 1".parse::<Command>().is_err());
+}
+
+#[test]
+fn the_console_stops_between_instructions_only() {
+    // The LDA's execute cycle must not count as reaching line 1
+    let mut computer = machine("LDA 0x010\nNOP\nHLT");
+    computer.set_register_trace(false);
+    computer.pause(PauseReason::Console);
+    let id = computer.add_breakpoint(1);
+
+    let mut debugger = Debugger::new();
+    debugger.queue_commands("continue\nregs\ncontinue\nquit\n");
+    computer.run_console(&mut debugger);
+    assert_eq!(computer.breakpoints().get(id).unwrap().hits, 1);
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Halted(HaltReason::Instruction)
+    );
+}
@@ -0,0 +1,33 @@
+//! Fixtures shared by the integration tests
+//!
+//! Each test file keeps its own program source and builds machines from it here.
+
+// Every test crate compiles this module, and none uses all of it
+#![allow(dead_code)]
+
+use blue::{BlueComputer, ExecutionMode, IsaProfile, asm};
+
+/// Address of the label `name` in `source`
+pub fn symbol(source: &str, name: &str) -> u16 {
+    asm::assemble(source).unwrap().symbols[name]
+}
+
+/// A powered-on machine with `source` assembled into memory
+pub fn machine(source: &str) -> BlueComputer {
+    machine_with(source, IsaProfile::default(), ExecutionMode::CycleAccurate)
+}
+
+/// A powered-on machine running `source` under `profile` in `mode`
+pub fn machine_with(source: &str, profile: IsaProfile, mode: ExecutionMode) -> BlueComputer {
+    loaded(&asm::assemble(source).unwrap().words, profile, mode)
+}
+
+/// A powered-on machine with `words` loaded from address 0
+pub fn loaded(words: &[u16], profile: IsaProfile, mode: ExecutionMode) -> BlueComputer {
+    let mut computer = BlueComputer::new();
+    computer.set_profile(profile);
+    computer.set_execution_mode(mode);
+    computer.load_program(words);
+    computer.press_on();
+    computer
+}
@@ -0,0 +1,117 @@
+use blue::{
+    BlueComputer, RegisterName,
+    debugger::{Command, Debugger, Flow, Location},
+    journal::Granularity,
+};
+
+fn parse(line: &str) -> Command {
+    line.parse().unwrap()
+}
+
+fn addresses(computer: &BlueComputer) -> Vec<u16> {
+    computer.breakpoints().iter().map(|b| b.address).collect()
+}
+
+#[test]
+fn long_forms_aliases_and_short_forms_agree() {
+    for line in [
+        "break 100",
+        "b 100",
+        "b100",
+        "BREAK 0x64",
+        "break 0b1100100",
+    ] {
+        let command = Command::Break {
+            address: 100,
+            condition: None,
+            temporary: false,
+        };
+        assert_eq!(parse(line), command, "{line}");
+    }
+    assert_eq!(parse("l16"), Command::List(Some(16)));
+    assert_eq!(parse("c"), Command::Continue);
+    assert_eq!(parse("exit"), Command::Quit);
+    assert_eq!(
+        parse("history 8 tick"),
+        Command::History {
+            depth: 8,
+            granularity: Granularity::Tick
+        }
+    );
+}
+
+#[test]
+fn examine_and_deposit_take_registers_or_addresses() {
+    assert_eq!(
+        parse("xA 42"),
+        Command::Deposit {
+            location: Location::Register(RegisterName::A),
+            values: vec![42],
+        }
+    );
+    assert_eq!(
+        parse("deposit 0x10 0b11 7"),
+        Command::Deposit {
+            location: Location::Memory(0x10),
+            values: vec![3, 7],
+        }
+    );
+    assert_eq!(
+        parse("e ipc"),
+        Command::Examine {
+            location: Location::Register(RegisterName::Ipc),
+            count: 1,
+        }
+    );
+}
+
+#[test]
+fn bad_input_is_reported() {
+    for line in [
+        "frob",
+        "who",
+        "break 4096",
+        "break 1 2",
+        "xA 1 2",
+        "xQ 4",
+        "deposit 0 0x10000",
+        "history 3 cycles",
+    ] {
+        assert!(line.parse::<Command>().is_err(), "{line}");
+    }
+}
+
+#[test]
+fn commands_change_the_machine() {
+    let mut computer = BlueComputer::new();
+    let mut debugger = Debugger::new();
+    for line in [
+        "deposit 5 0x6001 0x0000",
+        "xPC 5",
+        "break 9  # a comment",
+        "",
+    ] {
+        assert_eq!(debugger.run_line(&mut computer, line), Flow::Prompt);
+    }
+    assert_eq!(computer.read_memory(5), Some(0x6001));
+    assert_eq!(computer.register(RegisterName::Pc), 5);
+    assert_eq!(addresses(&computer), [9]);
+
+    // The machine has to be switched on before it can continue
+    assert_eq!(debugger.run_line(&mut computer, "c"), Flow::Prompt);
+    assert_eq!(debugger.run_line(&mut computer, "on"), Flow::Run);
+    assert_eq!(debugger.run_line(&mut computer, "s"), Flow::Step);
+    assert_eq!(debugger.run_line(&mut computer, "q"), Flow::Quit);
+}
+
+#[test]
+fn a_script_stops_at_its_first_error() {
+    let mut computer = BlueComputer::new();
+    let mut debugger = Debugger::new();
+    debugger.queue_commands("break 1\nbogus\nbreak 2\n");
+    while debugger.queued() > 0 {
+        let line = debugger.next_line().unwrap().unwrap();
+        debugger.run_line(&mut computer, &line);
+    }
+    assert_eq!(addresses(&computer), [1]);
+}
@@ -0,0 +1,120 @@
+use blue::{
+    IsaProfile, asm,
+    disasm::{self, Options},
+};
+use std::{collections::BTreeMap, fs, process::Command};
+
+/// Code with a data word after a JMP and another after the HLT
+const PROGRAM: &str = "
+        start:  JMP main
+        msg:    DW 0x6001
+        main:   LDA msg
+                SRJ sub
+                HLT
+                DW 0x1234
+        sub:    JMA start
+                JMP main
+";
+
+fn options(entry: Option<u16>) -> Options {
+    Options {
+        entry,
+        symbols: asm::assemble(PROGRAM).unwrap().symbols,
+        profile: IsaProfile::Foster,
+    }
+}
+
+fn texts(words: &[u16], options: &Options) -> Vec<String> {
+    disasm::disassemble(words, 0..words.len(), options)
+        .into_iter()
+        .map(|line| line.text)
+        .collect()
+}
+
+#[test]
+fn code_is_found_by_following_execution() {
+    let words = asm::assemble(PROGRAM).unwrap().words;
+    let code = disasm::find_code(&words, 0, IsaProfile::Foster);
+    assert_eq!(code, [true, false, true, true, true, false, true, true]);
+
+    // Entered at `sub`, the JMA reaches everything the entry at 0 does
+    assert_eq!(disasm::find_code(&words, 6, IsaProfile::Foster), code);
+    // Entered after the JMP, the data word is taken for an LDA
+    assert!(disasm::find_code(&words, 1, IsaProfile::Foster)[1]);
+}
+
+#[test]
+fn listings_name_labels_and_show_data() {
+    let words = asm::assemble(PROGRAM).unwrap().words;
+    assert_eq!(
+        texts(&words, &options(Some(0))),
+        [
+            "JMP main",
+            "DW 0x6001",
+            "LDA msg",
+            "SRJ sub",
+            "HLT",
+            "DW 0x1234",
+            "JMA start",
+            "JMP main",
+        ]
+    );
+    let lines = disasm::disassemble(&words, 1..3, &options(None));
+    assert_eq!(lines[0].to_string(), "001  6001  msg:        LDA msg");
+    assert!(lines[0].is_code);
+    assert_eq!(lines[1].to_string(), "002  6001  main:       LDA msg");
+}
+
+#[test]
+fn words_are_formatted_for_the_profile() {
+    let labels = BTreeMap::from([(0x010, "x".to_string())]);
+    let format = |word, profile| disasm::format_instruction(word, profile, &labels);
+    assert_eq!(format(0x1010, IsaProfile::Foster), "ADD x");
+    assert_eq!(format(0x1011, IsaProfile::Foster), "ADD 0x011");
+    assert_eq!(format(0xC002, IsaProfile::Foster), "OUT 0x02");
+    assert_eq!(format(0xF123, IsaProfile::Extended), "SUB 0x23");
+    assert_eq!(format(0xF110, IsaProfile::Extended), "SUB x");
+    assert_eq!(format(0xF900, IsaProfile::Extended), "DW 0xF900");
+    // Operand bits on an instruction without an operand cannot be assembled
+    assert_eq!(format(0xF123, IsaProfile::Foster), "DW 0xF123");
+    assert_eq!(format(0x0123, IsaProfile::Foster), "DW 0x0123");
+}
+
+#[test]
+fn listings_assemble_back_to_the_same_words() {
+    let original = asm::assemble(PROGRAM).unwrap().words;
+    for entry in [Some(0), None] {
+        let source: String = disasm::disassemble(&original, 0..original.len(), &options(entry))
+            .into_iter()
+            .map(|line| match line.label {
+                Some(label) => format!("{label}: {}\n", line.text),
+                None => format!("{}\n", line.text),
+            })
+            .collect();
+        assert_eq!(asm::assemble(&source).unwrap().words, original, "{source}");
+    }
+}
+
+#[test]
+fn disasm_command_lists_a_source_file() {
+    let path = std::env::temp_dir().join(format!("blue-{}-disasm.asm", std::process::id()));
+    fs::write(&path, PROGRAM).unwrap();
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_blue"))
+            .arg("disasm")
+            .arg(&path)
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let listing = run(&[]);
+    assert_eq!(listing.lines().count(), 8);
+    assert!(listing.contains("001  6001  msg:        DW 0x6001\n"));
+    assert!(listing.contains("006  9000  sub:        JMA start\n"));
+    assert!(run(&["--all"]).contains("001  6001  msg:        LDA msg\n"));
+    assert!(run(&["--entry", "4"]).contains("000  A002  start:      DW 0xA002\n"));
+    fs::remove_file(&path).unwrap();
+}
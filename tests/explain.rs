@@ -0,0 +1,96 @@
+mod common;
+
+use blue::{
+    BlueComputer, ExecutionMode, Instruction, IsaProfile, OverflowPolicy, State, StopReason,
+    debugger::Command, explain::MicroEvent, microcode::MicroOp,
+};
+use common::loaded;
+
+/// LDA 4, ADD 4, JMA 0, HLT, 0x4000
+const PROGRAM: [u16; 5] = [0x6004, 0x1004, 0x9000, 0x0000, 0x4000];
+
+fn machine(mode: ExecutionMode) -> BlueComputer {
+    let mut computer = loaded(&PROGRAM, IsaProfile::Flags, mode);
+    computer.set_overflow_policy(Some(OverflowPolicy::Flag));
+    computer
+}
+
+/// Events of the next instruction
+fn instruction(computer: &mut BlueComputer) -> Vec<MicroEvent> {
+    computer.step_instruction();
+    computer.take_micro_events()
+}
+
+#[test]
+fn explain_mode_is_off_by_default() {
+    let mut computer = machine(ExecutionMode::CycleAccurate);
+    assert!(!computer.explain_mode());
+    assert!(instruction(&mut computer).is_empty());
+
+    computer.set_explain_mode(true);
+    computer.step_tick();
+    computer.set_explain_mode(false);
+    assert!(computer.take_micro_events().is_empty());
+}
+
+#[test]
+fn events_carry_the_values_moved() {
+    let mut computer = machine(ExecutionMode::CycleAccurate);
+    computer.set_explain_mode(true);
+    let lda = instruction(&mut computer);
+    let fetch = lda
+        .iter()
+        .find(|event| event.op == MicroOp::ReadMemory)
+        .unwrap();
+    assert_eq!(fetch.tick.opcode, Instruction::Lda.opcode());
+    assert_eq!((fetch.tick.state, fetch.tick.tick), (State::Fetch, 4));
+    assert_eq!((fetch.address, fetch.value), (Some(0x000), Some(0x6004)));
+    assert_eq!(
+        fetch.to_string(),
+        "tick 4 (FETCH): MBR ← RAM[MAR=0x000] = 0x6004"
+    );
+
+    let add = instruction(&mut computer);
+    let sum = add.iter().find(|event| event.op == MicroOp::Add).unwrap();
+    assert_eq!(
+        sum.to_string(),
+        "tick 6 (EXECUTE): A ← Z + MBR = 0x8000, overflow set, negative set"
+    );
+    assert_eq!(sum.flags_cleared, 0);
+}
+
+#[test]
+fn guarded_operations_that_do_not_run_are_skipped() {
+    let mut computer = machine(ExecutionMode::CycleAccurate);
+    computer.write_memory(4, 0x0001);
+    computer.set_explain_mode(true);
+    instruction(&mut computer);
+    instruction(&mut computer);
+    let jma = instruction(&mut computer);
+    let jumps: Vec<_> = jma
+        .iter()
+        .filter(|event| event.condition.is_some())
+        .collect();
+    assert!(!jumps.is_empty());
+    assert!(jumps.iter().all(|event| !event.performed));
+    assert!(jumps[0].to_string().ends_with(", skipped"));
+    assert_eq!(computer.pc(), 3);
+}
+
+#[test]
+fn fast_mode_is_narrated_tick_by_tick() {
+    let mut accurate = machine(ExecutionMode::CycleAccurate);
+    let mut fast = machine(ExecutionMode::Fast);
+    accurate.set_explain_mode(true);
+    fast.set_explain_mode(true);
+    assert_eq!(accurate.run_until(10), StopReason::CycleLimit);
+    assert_eq!(fast.run_until(10), StopReason::CycleLimit);
+    assert_eq!(accurate.take_micro_events(), fast.take_micro_events());
+}
+
+#[test]
+fn console_command_for_explain() {
+    assert_eq!("explain on".parse(), Ok(Command::Explain(Some(true))));
+    assert_eq!("explain".parse(), Ok(Command::Explain(None)));
+    assert!("explain maybe".parse::<Command>().is_err());
+}
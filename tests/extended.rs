@@ -0,0 +1,125 @@
+mod common;
+
+use blue::{BlueComputer, ExecutionMode, Instruction, IsaProfile, StopReason, asm};
+use common::machine_with;
+
+fn run(source: &str, profile: IsaProfile) -> BlueComputer {
+    let mut computer = machine_with(source, profile, ExecutionMode::CycleAccurate);
+    assert_eq!(computer.run_until(1_000), StopReason::Halted);
+    computer
+}
+
+#[test]
+fn extended_instructions_encode_under_nop() {
+    assert_eq!(Instruction::Sub.encode(0x23), 0xF123);
+    assert_eq!(Instruction::Cmp.encode(0x45), 0xF245);
+    assert_eq!(Instruction::Nop.encode(0), 0xF000);
+
+    let assembly = asm::assemble("SUB 0x23\nCMP 0x45\nNOP").unwrap();
+    assert_eq!(assembly.words, [0xF123, 0xF245, 0xF000]);
+}
+
+#[test]
+fn extended_decoding_is_opt_in() {
+    assert_eq!(
+        Instruction::decode(0xF123, IsaProfile::Foster),
+        Ok(Instruction::Nop)
+    );
+    assert_eq!(
+        Instruction::decode(0xF123, IsaProfile::Extended),
+        Ok(Instruction::Sub)
+    );
+    assert_eq!(
+        Instruction::decode(0xF245, IsaProfile::Extended),
+        Ok(Instruction::Cmp)
+    );
+    assert_eq!(
+        Instruction::decode(0xF000, IsaProfile::Extended),
+        Ok(Instruction::Nop)
+    );
+    assert!(Instruction::decode(0xFF00, IsaProfile::Extended).is_err());
+}
+
+#[test]
+fn extended_operand_is_page_zero() {
+    assert_eq!(Instruction::Sub.operand(0xF1AB), 0xAB);
+    assert_eq!(Instruction::Lda.operand(0x6ABC), 0xABC);
+    assert!(asm::assemble("SUB 0x100").is_err());
+}
+
+#[test]
+fn sub_produces_negative_results() {
+    let source = "
+        LDA x
+        SUB y
+        STA diff
+        HLT
+x:      DW 3
+y:      DW 5
+diff:   DW 0
+";
+    let computer = run(source, IsaProfile::Extended);
+    assert_eq!(computer.a(), 0xFFFE);
+    assert_eq!(computer.read_memory(6), Some(0xFFFE));
+}
+
+#[test]
+fn cmp_sets_flags_without_changing_a() {
+    let computer = run("LDA x\nCMP x\nHLT\nx: DW 7", IsaProfile::Extended);
+    assert_eq!(computer.a(), 7);
+    assert_eq!(computer.flags() & 0b0001, 0b0001);
+}
+
+#[test]
+fn sub_is_a_nop_on_the_original_machine() {
+    let computer = run("LDA x\nSUB x\nHLT\nx: DW 7", IsaProfile::Foster);
+    assert_eq!(computer.a(), 7);
+}
+
+#[test]
+fn jmz_ends_a_countdown() {
+    let source = "
+loop:   LDA n
+        SUB one
+        STA n
+        JMZ done
+        LDA steps
+        ADD one
+        STA steps
+        JMP loop
+done:   HLT
+n:      DW 5
+one:    DW 1
+steps:  DW 0
+";
+    let computer = run(source, IsaProfile::Extended);
+    assert_eq!(computer.read_memory(11), Some(4));
+}
+
+#[test]
+fn jmc_and_jmv_follow_add() {
+    let source = "
+        LDA max
+        ADD one         ; 0x7FFF + 1 overflows
+        JMV wrapped
+        HLT
+wrapped: LDA ones
+        ADD one         ; 0xFFFF + 1 carries
+        JMC carried
+        HLT
+carried: LDA done
+        HLT
+max:    DW 0x7FFF
+ones:   DW 0xFFFF
+one:    DW 1
+done:   DW 0x00D0
+";
+    let computer = run(source, IsaProfile::Extended);
+    assert_eq!(computer.a(), 0x00D0);
+}
+
+#[test]
+fn flag_jumps_are_nops_on_the_original_machine() {
+    let computer = run("JMZ end\nLDA x\nend: HLT\nx: DW 7", IsaProfile::Foster);
+    assert_eq!(computer.a(), 7);
+}
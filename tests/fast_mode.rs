@@ -0,0 +1,164 @@
+mod common;
+
+use blue::{BlueComputer, ExecutionMode, IsaProfile, StopReason, asm, device::IoDevice};
+use common::loaded;
+use std::io;
+
+/// Loopback device: INP returns a running counter, OUT bytes are recorded
+#[derive(Default)]
+struct Loopback {
+    next: u8,
+    written: Vec<u8>,
+    ticks: u64,
+}
+
+impl IoDevice for Loopback {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        self.next = self.next.wrapping_add(1);
+        Ok(self.next)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.written.push(byte);
+        Ok(())
+    }
+
+    fn clock(&mut self) {
+        self.ticks += 1;
+    }
+}
+
+fn machine(words: &[u16], profile: IsaProfile, mode: ExecutionMode) -> BlueComputer {
+    let mut computer = loaded(words, profile, mode);
+    computer.bus_mut().attach(1, Loopback::default());
+    computer.set_switches(0x1234);
+    computer
+}
+
+fn assert_same(slow: &BlueComputer, fast: &BlueComputer, context: &str) {
+    assert_eq!(slow.pc(), fast.pc(), "PC differs {context}");
+    assert_eq!(slow.a(), fast.a(), "A differs {context}");
+    assert_eq!(slow.ir(), fast.ir(), "IR differs {context}");
+    assert_eq!(slow.flags(), fast.flags(), "flags differ {context}");
+    assert_eq!(
+        slow.clock_pulse(),
+        fast.clock_pulse(),
+        "tick differs {context}"
+    );
+    assert_eq!(
+        slow.is_powered(),
+        fast.is_powered(),
+        "power differs {context}"
+    );
+    assert!(slow.ram() == fast.ram(), "RAM differs {context}");
+
+    let device = |computer: &BlueComputer| {
+        let device = computer.bus().device::<Loopback>(1).unwrap();
+        (device.next, device.written.clone(), device.ticks)
+    };
+    assert_eq!(device(slow), device(fast), "I/O differs {context}");
+}
+
+/// Run both modes side by side for `cycles` cycles in chunks of `chunk`, comparing
+/// after each
+fn differential(words: &[u16], profile: IsaProfile, chunk: u64, cycles: u64) {
+    let mut slow = machine(words, profile, ExecutionMode::CycleAccurate);
+    let mut fast = machine(words, profile, ExecutionMode::Fast);
+    for i in 0..cycles.div_ceil(chunk) {
+        let context = format!("after chunk {i} ({profile})");
+        let stop = slow.run_until(chunk);
+        assert_eq!(stop, fast.run_until(chunk), "stop differs {context}");
+        assert_same(&slow, &fast, &context);
+        if stop != StopReason::CycleLimit {
+            return;
+        }
+    }
+}
+
+#[test]
+fn fast_mode_matches_on_assembled_programs() {
+    let sources = [
+        include_str!("../progs/sum.asm"),
+        "
+                LDA count
+        loop:   ADD one
+                STA count
+                XOR mask
+                AND mask
+                IOR one
+                NOT
+                RAL
+                CSA
+                JMA neg
+                JMP loop
+        neg:    SRJ sub
+                HLT
+        sub:    LDA count
+                JMP loop
+        count:  DW 0
+        one:    DW 1
+        mask:   DW 0x00FF
+        ",
+        "
+        loop:   INP 1
+                OUT 1
+                RAL
+                JMA done
+                JMP loop
+        done:   HLT
+        ",
+        "
+                LDA big
+                ADD big
+                HLT
+        big:    DW 0x7FFF
+        ",
+    ];
+    for source in sources {
+        let words = asm::assemble(source).unwrap().words;
+        for profile in IsaProfile::ALL {
+            for chunk in [1, 2, 3, 1_000] {
+                differential(&words, profile, chunk, 3_000);
+            }
+        }
+    }
+}
+
+#[test]
+fn fast_mode_matches_on_extended_programs() {
+    let source = "
+            LDA a
+            SUB b
+            CMP b
+            STA a
+            JMZ 0
+            JMC 0
+            JMV 0
+            JMP 0
+    a:      DW 5
+    b:      DW 3
+    ";
+    let words = asm::assemble(source).unwrap().words;
+    for chunk in [1, 2, 5, 1_000] {
+        differential(&words, IsaProfile::Extended, chunk, 3_000);
+    }
+}
+
+#[test]
+fn fast_mode_matches_on_random_memory() {
+    // xorshift, so the test needs no dependencies and is reproducible
+    let mut state = 0x2545_f491_u32;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+
+    for _ in 0..200 {
+        let words: Vec<u16> = (0..256).map(|_| (next() & 0xFFFF) as u16).collect();
+        let profile = IsaProfile::ALL[next() as usize % IsaProfile::ALL.len()];
+        let chunk = u64::from(next() % 16 + 1);
+        differential(&words, profile, chunk, 1_000);
+    }
+}
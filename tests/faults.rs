@@ -0,0 +1,36 @@
+mod common;
+
+use blue::{ExecutionMode, Fault, FaultKind, IsaProfile, RunState, StopReason};
+use common::machine_with;
+
+/// Run `source` to its first stop in both execution modes, which must agree
+fn fault(source: &str, profile: IsaProfile) -> Fault {
+    let [accurate, fast] = [ExecutionMode::CycleAccurate, ExecutionMode::Fast].map(|mode| {
+        let mut computer = machine_with(source, profile, mode);
+        let StopReason::Fault(fault) = computer.run_until(100) else {
+            panic!("{mode:?}: no fault");
+        };
+        assert_eq!(computer.run_state(), &RunState::Faulted(fault.clone()));
+        fault
+    });
+    assert_eq!(accurate, fast);
+    accurate
+}
+
+#[test]
+fn running_off_the_end_of_memory_faults() {
+    let fault = fault("JMP last\nORG 0xFFF\nlast: NOP", IsaProfile::Foster);
+    assert_eq!(fault.kind, FaultKind::AddressOutOfRange(0x1000));
+    assert_eq!((fault.pc, fault.ir), (0x1001, 0xF000));
+    assert_eq!(
+        fault.to_string(),
+        "address 1000 out of range (PC: 1001 IR: f000)"
+    );
+}
+
+#[test]
+fn undefined_extended_opcodes_fault() {
+    let fault = fault("NOP\nDW 0xF900\nHLT", IsaProfile::Extended);
+    assert_eq!(fault.kind, FaultKind::InvalidOpcode(0xF900));
+    assert_eq!((fault.pc, fault.ir), (2, 0xF900));
+}
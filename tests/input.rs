@@ -0,0 +1,158 @@
+mod common;
+
+use blue::{
+    BlueComputer, FaultKind, HaltReason, IoRequest, PauseReason, RunState, StopReason,
+    debugger::Debugger,
+    device::EndOfInputPolicy,
+    input::{InputFormat, ScriptedInput},
+};
+use common::machine;
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Copy bytes from device 1 to device 2 until a zero byte, then halt
+const ECHO: &str = "
+        loop:   INP 1
+                JMA done        ; bytes >= 0x80 stop the loop
+                OUT 2
+                JMP loop
+        done:   HLT
+";
+
+fn echo(input: ScriptedInput, policy: EndOfInputPolicy) -> BlueComputer {
+    let mut computer = machine(ECHO);
+    computer.bus_mut().attach(1, input);
+    computer.bus_mut().set_end_of_input_policy(policy);
+    computer
+}
+
+/// Drain OUT transfers left for the host, returning the bytes and the final stop
+fn run(computer: &mut BlueComputer) -> (Vec<u8>, StopReason) {
+    let mut output = Vec::new();
+    loop {
+        match computer.run_until(10_000) {
+            StopReason::WaitingForIo => match computer.pending_io() {
+                Some(IoRequest::Output { byte, .. }) => {
+                    output.push(byte);
+                    computer.complete_output();
+                }
+                _ => return (output, StopReason::WaitingForIo),
+            },
+            stop => return (output, stop),
+        }
+    }
+}
+
+#[test]
+fn here_document_feeds_inp() {
+    let input = ScriptedInput::from_hex(
+        "
+        48 69   # 'H' 'i'
+        0x21
+        80
+        ",
+    )
+    .unwrap();
+    let mut computer = echo(input, EndOfInputPolicy::Fault);
+    assert_eq!(run(&mut computer), (b"Hi!".to_vec(), StopReason::Halted));
+}
+
+#[test]
+fn raw_input_from_a_reader() {
+    let input = ScriptedInput::from_reader(&b"ok\x80"[..], InputFormat::Raw).unwrap();
+    let mut computer = echo(input, EndOfInputPolicy::Fault);
+    assert_eq!(run(&mut computer), (b"ok".to_vec(), StopReason::Halted));
+}
+
+#[test]
+fn malformed_hex_is_rejected() {
+    assert!(ScriptedInput::from_hex("12 xyz").is_err());
+    assert!(ScriptedInput::from_hex("100").is_err());
+}
+
+#[test]
+fn exhausted_input_follows_the_policy() {
+    let mut computer = echo(ScriptedInput::new(*b"a"), EndOfInputPolicy::Fault);
+    match run(&mut computer) {
+        (output, StopReason::Fault(fault)) => {
+            assert_eq!(output, b"a");
+            assert_eq!(fault.kind, FaultKind::EndOfInput(1));
+        }
+        other => panic!("expected a fault, got {other:?}"),
+    }
+
+    let mut computer = echo(ScriptedInput::new(*b"a"), EndOfInputPolicy::Halt);
+    assert_eq!(run(&mut computer), (b"a".to_vec(), StopReason::Halted));
+
+    let mut computer = echo(ScriptedInput::new(*b"a"), EndOfInputPolicy::Wait);
+    assert_eq!(
+        run(&mut computer),
+        (b"a".to_vec(), StopReason::WaitingForIo)
+    );
+    assert_eq!(computer.pending_io(), Some(IoRequest::Input { device: 1 }));
+    computer
+        .bus_mut()
+        .device_mut::<ScriptedInput>(1)
+        .unwrap()
+        .push(b"b\x80");
+    assert_eq!(run(&mut computer), (b"b".to_vec(), StopReason::Halted));
+}
+
+#[test]
+fn the_console_reads_commands_from_its_own_source() {
+    let input = ScriptedInput::from_hex("48 69 80").unwrap();
+    let mut computer = echo(input, EndOfInputPolicy::Fault);
+    computer.set_register_trace(false);
+    computer.pause(PauseReason::Console);
+    let id = computer.add_breakpoint(4);
+
+    let mut debugger = Debugger::new();
+    debugger.set_command_source(&b"continue\ncontinue\nquit\n"[..]);
+    computer.run_console(&mut debugger);
+    assert_eq!(computer.breakpoints().get(id).unwrap().hits, 1);
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Halted(HaltReason::Instruction)
+    );
+}
+
+#[test]
+fn piped_input_leaves_the_console_its_commands() {
+    let dir = std::env::temp_dir();
+    let program = dir.join(format!("blue-{}-echo.asm", std::process::id()));
+    let commands = dir.join(format!("blue-{}-echo.commands", std::process::id()));
+    fs::write(&program, ECHO).unwrap();
+    fs::write(&commands, "break 4\ncontinue\nregs\ncontinue\nquit\n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_blue"))
+        .arg(&program)
+        .args(["--quiet", "--input", "-", "--input-device", "1"])
+        .args([
+            "--output",
+            "-",
+            "--output-device",
+            "2",
+            "--output-format",
+            "ascii",
+        ])
+        .arg("--commands")
+        .arg(&commands)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"48 69 80").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // The INP bytes came from the pipe and every command still ran
+    assert!(stdout.contains("HiStopped at line 4"), "{stdout}");
+    assert!(stdout.contains("DIL: 80"), "{stdout}");
+    assert!(stdout.contains("> quit"), "{stdout}");
+
+    fs::remove_file(&program).unwrap();
+    fs::remove_file(&commands).unwrap();
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use blue::{
+    BlueComputer, ExecutionMode, INTERRUPT_VECTOR, Instruction, IsaProfile, StopReason,
+    input::ScriptedInput,
+};
+use common::{machine_with, symbol};
+
+/// Count bytes delivered by interrupts from device 1 while the main loop spins
+const COUNTER: &str = "
+        start:  JMP main
+        isr:    INP 1           ; the vector: take the waiting byte
+                STA last
+                LDA count
+                ADD one
+                STA count
+                RTI
+        main:   EIN
+        spin:   JMP spin
+        count:  DW 0
+        last:   DW 0
+        one:    DW 1
+";
+
+fn counter(profile: IsaProfile, mode: ExecutionMode, bytes: &[u8]) -> BlueComputer {
+    assert_eq!(symbol(COUNTER, "isr"), INTERRUPT_VECTOR);
+
+    let mut input = ScriptedInput::new(bytes);
+    input.set_interrupts(true);
+    let mut computer = machine_with(COUNTER, profile, mode);
+    computer.bus_mut().attach(1, input);
+    computer
+}
+
+#[test]
+fn interrupts_deliver_every_byte() {
+    let mut computer = counter(IsaProfile::Extended, ExecutionMode::CycleAccurate, b"abc");
+    assert_eq!(computer.bus().pending_interrupt(), Some(1));
+    assert_eq!(computer.run_until(500), StopReason::CycleLimit);
+
+    assert_eq!(computer.read_memory(symbol(COUNTER, "count")), Some(3));
+    assert_eq!(
+        computer.read_memory(symbol(COUNTER, "last")),
+        Some(u16::from(b'c') << 8)
+    );
+    assert_eq!(computer.bus().pending_interrupt(), None);
+    assert!(computer.interrupts_enabled());
+    assert_eq!(computer.ipc(), symbol(COUNTER, "spin"));
+}
+
+#[test]
+fn fast_mode_takes_interrupts_at_the_same_points() {
+    let mut accurate = counter(IsaProfile::Extended, ExecutionMode::CycleAccurate, b"xyz");
+    let mut fast = counter(IsaProfile::Extended, ExecutionMode::Fast, b"xyz");
+    for _ in 0..50 {
+        assert_eq!(accurate.run_until(7), fast.run_until(7));
+        assert_eq!(accurate.save_state(), fast.save_state());
+    }
+}
+
+#[test]
+fn interrupts_need_the_extended_profile() {
+    let mut computer = counter(IsaProfile::Flags, ExecutionMode::CycleAccurate, b"abc");
+    assert_eq!(computer.run_until(500), StopReason::CycleLimit);
+    assert_eq!(computer.read_memory(symbol(COUNTER, "count")), Some(0));
+    assert!(!computer.interrupts_enabled());
+}
+
+#[test]
+fn interrupts_stay_off_until_enabled() {
+    let mut computer = counter(IsaProfile::Extended, ExecutionMode::CycleAccurate, b"abc");
+    // Replace EIN with a jump straight into the loop
+    let jump = Instruction::Jmp.encode(symbol(COUNTER, "spin"));
+    computer.write_memory(symbol(COUNTER, "main"), jump);
+    assert_eq!(computer.run_until(500), StopReason::CycleLimit);
+    assert_eq!(computer.read_memory(symbol(COUNTER, "count")), Some(0));
+    assert_eq!(computer.bus().pending_interrupt(), Some(1));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use blue::{
+    BlueComputer, ExecutionMode, IsaProfile, StopReason, journal::Granularity, snapshot::Snapshot,
+};
+use common::machine_with;
+
+const SOURCE: &str = "
+        loop:   LDA count
+                ADD one
+                STA count
+                STA copy
+                JMP loop
+        count:  DW 0
+        one:    DW 1
+        copy:   DW 0
+";
+
+/// The loop with `depth` entries of history
+fn machine(depth: usize, granularity: Granularity, mode: ExecutionMode) -> BlueComputer {
+    let mut computer = machine_with(SOURCE, IsaProfile::Foster, mode);
+    computer.set_history(depth, granularity);
+    computer
+}
+
+#[test]
+fn reverse_step_retraces_every_instruction() {
+    for mode in [ExecutionMode::CycleAccurate, ExecutionMode::Fast] {
+        for granularity in [Granularity::Instruction, Granularity::Tick] {
+            let mut computer = machine(1_000, granularity, mode);
+            let mut states: Vec<Snapshot> = vec![computer.save_state()];
+            for _ in 0..20 {
+                assert_eq!(computer.step_instruction(), None);
+                states.push(computer.save_state());
+            }
+
+            states.pop();
+            while let Some(expected) = states.pop() {
+                assert!(computer.reverse_step());
+                assert_eq!(computer.save_state(), expected);
+            }
+            assert!(!computer.reverse_step());
+        }
+    }
+}
+
+#[test]
+fn step_back_undoes_single_ticks() {
+    let mut computer = machine(100, Granularity::Tick, ExecutionMode::CycleAccurate);
+    computer.run_until(3);
+    let before = computer.save_state();
+    computer.step_tick();
+    assert_ne!(computer.save_state(), before);
+    assert!(computer.step_back());
+    assert_eq!(computer.save_state(), before);
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut computer = machine(5, Granularity::Instruction, ExecutionMode::Fast);
+    computer.run_until(100);
+    assert_eq!(computer.history().len(), 5);
+    for _ in 0..5 {
+        assert!(computer.reverse_step());
+    }
+    assert!(!computer.reverse_step());
+}
+
+#[test]
+fn last_write_names_the_writing_instruction() {
+    let mut computer = machine(100, Granularity::Instruction, ExecutionMode::CycleAccurate);
+    computer.run_until(17);
+
+    let copy = computer.history().last_write(7).unwrap();
+    assert_eq!((copy.instruction, copy.old, copy.new), (3, 1, 2));
+    let count = computer.history().last_write(5).unwrap();
+    assert_eq!((count.instruction, count.old, count.new), (2, 1, 2));
+    assert!(computer.history().last_write(6).is_none());
+}
+
+#[test]
+fn reverse_continue_stops_at_breakpoints() {
+    let mut computer = machine(
+        1_000,
+        Granularity::Instruction,
+        ExecutionMode::CycleAccurate,
+    );
+    computer.add_breakpoint(3);
+    assert_eq!(computer.run_until(100), StopReason::Breakpoint(3));
+    assert_eq!(computer.run_until(100), StopReason::Breakpoint(3));
+    assert_eq!(computer.read_memory(5), Some(2));
+
+    assert_eq!(computer.reverse_continue(), Some(3));
+    assert_eq!(computer.read_memory(5), Some(1));
+    assert_eq!(computer.reverse_continue(), None);
+    assert_eq!(computer.pc(), 0);
+    assert_eq!(computer.read_memory(5), Some(0));
+}
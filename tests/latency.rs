@@ -0,0 +1,117 @@
+mod common;
+
+use blue::{
+    BlueComputer, StopReason,
+    device::{IoDevice, Latency},
+};
+use common::machine;
+use std::io;
+
+/// Three transfers on device 2, then halt
+const TRANSFERS: &str = "
+        OUT 2
+        OUT 2
+        INP 2
+        HLT
+";
+
+/// A device that takes `input` to answer INP and `output` to accept OUT
+struct Slow {
+    input: Latency,
+    output: Latency,
+}
+
+impl IoDevice for Slow {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        Ok(0x2A)
+    }
+
+    fn write_byte(&mut self, _byte: u8) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn latency(&self, input: bool) -> Latency {
+        if input { self.input } else { self.output }
+    }
+}
+
+/// Clock ticks taken to run TRANSFERS with `setup` applied
+fn ticks(setup: impl FnOnce(&mut BlueComputer)) -> u64 {
+    let mut computer = machine(TRANSFERS);
+    computer.bus_mut().attach(
+        2,
+        Slow {
+            input: Latency::Immediate,
+            output: Latency::Immediate,
+        },
+    );
+    setup(&mut computer);
+    let mut ticks = 1;
+    loop {
+        match computer.step_tick() {
+            None => ticks += 1,
+            Some(stop) => {
+                assert_eq!(stop, StopReason::Halted);
+                return ticks;
+            }
+        }
+    }
+}
+
+#[test]
+fn transfers_wait_out_the_latency() {
+    let immediate = ticks(|_| ());
+    let slow = ticks(|computer| {
+        computer.bus_mut().set_latency(2, Some(Latency::Ticks(20)));
+    });
+    // Each of the three transfers spins for three more EXECUTE cycles
+    assert_eq!(slow - immediate, 3 * 3 * 8);
+
+    let micros = ticks(|computer| {
+        computer.bus_mut().set_clock_rate(2_000_000);
+        computer.bus_mut().set_latency(2, Some(Latency::Micros(10)));
+    });
+    assert_eq!(micros, slow);
+}
+
+#[test]
+fn devices_declare_latency_per_direction() {
+    let immediate = ticks(|_| ());
+    let slow_input = ticks(|computer| {
+        computer.bus_mut().attach(
+            2,
+            Slow {
+                input: Latency::Ticks(16),
+                output: Latency::Immediate,
+            },
+        );
+    });
+    assert_eq!(slow_input - immediate, 2 * 8);
+
+    let overridden = ticks(|computer| {
+        computer.bus_mut().attach(
+            2,
+            Slow {
+                input: Latency::Ticks(16),
+                output: Latency::Ticks(16),
+            },
+        );
+        computer.bus_mut().set_latency(2, Some(Latency::Immediate));
+    });
+    assert_eq!(overridden, immediate);
+}
+
+#[test]
+fn parse_latency() {
+    assert_eq!("40".parse(), Ok(Latency::Ticks(40)));
+    assert_eq!("100us".parse(), Ok(Latency::Micros(100)));
+    assert!("fast".parse::<Latency>().is_err());
+    assert_eq!(Latency::Micros(3).ticks(500_000), 2);
+}
+
+#[test]
+fn huge_latencies_saturate() {
+    let latency: Latency = "99999999999999us".parse().unwrap();
+    assert_eq!(latency.ticks(1_000_000), 99_999_999_999_999);
+    assert_eq!(Latency::Micros(u64::MAX).ticks(2_000_000), u64::MAX);
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use blue::{
+    ExecutionMode, Instruction, IsaProfile, StopReason,
+    microcode::{Condition, MicroOp, MicroStep, Microcode},
+};
+use common::machine_with;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// ADD replaced by an inclusive OR
+const ADD_AS_IOR: &str = "
+# ADD now ORs its operand in
+ADD  FETCH    5  Z<-0
+ADD  FETCH    6  Z<-A
+ADD  FETCH    7  MAR<-IR, EXECUTE
+ADD  EXECUTE  3  MBR<-RAM[MAR]
+ADD  EXECUTE  6  A<-Z|MBR
+ADD  EXECUTE  7  MAR<-PC, FETCH
+";
+
+fn overlaid(text: &str) -> Result<Microcode, String> {
+    let mut microcode = (*Microcode::builtin()).clone();
+    microcode.overlay(text).map_err(|e| e.to_string())?;
+    Ok(microcode)
+}
+
+#[test]
+fn overlays_replace_only_the_opcodes_they_mention() {
+    let builtin = Microcode::builtin();
+    let microcode = overlaid(ADD_AS_IOR).unwrap();
+    let add = Instruction::Add.opcode();
+    assert_ne!(microcode.routine(add), builtin.routine(add));
+    assert_eq!(
+        microcode.routine(add).unwrap().execute[6],
+        [MicroStep {
+            condition: None,
+            op: MicroOp::Ior
+        }]
+    );
+    // The old ADD routine had a step at EXECUTE tick 2 that is gone now
+    assert!(microcode.routine(add).unwrap().execute[2].is_empty());
+    for opcode in (0..16).filter(|&opcode| opcode != add) {
+        assert_eq!(
+            microcode.routine(opcode),
+            builtin.routine(opcode),
+            "{opcode}"
+        );
+    }
+    assert_eq!(microcode.common(), builtin.common());
+
+    // Numbers name opcodes too, and a new one becomes defined
+    let microcode = overlaid("30 EXECUTE 0 HALT").unwrap();
+    assert!(builtin.routine(30).is_none());
+    assert!(microcode.routine(30).is_some());
+}
+
+#[test]
+fn errors_give_the_line() {
+    for (text, message) in [
+        (
+            "\n# comment\nLDA FETCH 8 A<-0",
+            "line 3: tick '8' is not 0-7",
+        ),
+        (
+            "LDA DECODE 1 A<-0",
+            "line 1: state 'DECODE' is not FETCH or EXECUTE",
+        ),
+        (
+            "\nLDA FETCH 1 A<-B",
+            "line 2: unknown micro-operation 'A<-B'",
+        ),
+        (
+            "LDA FETCH 1 [SIGN] A<-0",
+            "line 1: unknown condition 'SIGN'",
+        ),
+        ("FOO FETCH 1 A<-0", "line 1: unknown opcode 'FOO'"),
+        ("31 FETCH 1 A<-0", "line 1: unknown opcode '31'"),
+        (
+            "LDA FETCH",
+            "line 1: expected '<opcode> <FETCH|EXECUTE> <tick> <ops>'",
+        ),
+    ] {
+        assert_eq!(overlaid(text).unwrap_err(), message, "{text}");
+    }
+}
+
+#[test]
+fn guarded_steps_only_run_when_their_condition_holds() {
+    assert_eq!(
+        "[zero] pc <- pc+1".parse(),
+        Ok(MicroStep {
+            condition: Some(Condition::Zero),
+            op: MicroOp::IncrementPc
+        })
+    );
+
+    // NOP skips the next word when the last result was zero
+    let microcode = overlaid("NOP FETCH 7 [ZERO] PC<-PC+1, MAR<-PC").unwrap();
+    for (mask, a, pc) in [(0, 1, 6), (1, 1, 4)] {
+        let source = format!(
+            "
+                    LDA one
+                    AND mask
+                    NOP
+                    HLT
+                    LDA one
+                    HLT
+            one:    DW 1
+            mask:   DW {mask}
+            "
+        );
+        let mut computer = machine_with(&source, IsaProfile::Flags, ExecutionMode::CycleAccurate);
+        computer.set_microcode(microcode.clone());
+        assert_eq!(computer.run_until(100), StopReason::Halted);
+        assert_eq!((computer.a(), computer.pc()), (a, pc), "mask {mask}");
+    }
+}
+
+#[test]
+fn the_listing_loads_back_as_the_same_table() {
+    for microcode in [
+        (*Microcode::builtin()).clone(),
+        overlaid(ADD_AS_IOR).unwrap(),
+    ] {
+        let mut reloaded = Microcode::default();
+        reloaded.overlay(&microcode.to_string()).unwrap();
+        assert_eq!(reloaded, microcode);
+    }
+}
+
+#[test]
+fn microcode_files_change_how_programs_run() {
+    let dir = std::env::temp_dir();
+    let program = dir.join(format!("blue-{}-microcode.asm", std::process::id()));
+    let table = dir.join(format!("blue-{}-microcode.txt", std::process::id()));
+    fs::write(&program, "LDA x\nADD x\nOUT 0\nHLT\nx: DW 0x0300").unwrap();
+    fs::write(&table, ADD_AS_IOR).unwrap();
+
+    // OUT sends the high byte of A: 3 + 3, or 3 | 3
+    let printed = |microcode: Option<&Path>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_blue"));
+        // Past the HLT the console reads commands until the end of input
+        command
+            .arg(&program)
+            .args(["--quiet", "--output", "-"])
+            .stdin(Stdio::null());
+        if let Some(path) = microcode {
+            command.arg("--microcode").arg(path);
+        }
+        let stdout = String::from_utf8(command.output().unwrap().stdout).unwrap();
+        stdout.lines().last().unwrap_or_default().to_string()
+    };
+    assert_eq!(printed(None), "06");
+    assert_eq!(printed(Some(&table)), "03");
+
+    fs::write(&table, "ADD FETCH 9 A<-0").unwrap();
+    let failed = Command::new(env!("CARGO_BIN_EXE_blue"))
+        .arg(&program)
+        .arg("--microcode")
+        .arg(&table)
+        .output()
+        .unwrap();
+    assert!(!failed.status.success());
+    assert!(
+        String::from_utf8(failed.stdout)
+            .unwrap()
+            .contains("line 1: tick '9' is not 0-7")
+    );
+
+    fs::remove_file(&program).unwrap();
+    fs::remove_file(&table).unwrap();
+}
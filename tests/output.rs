@@ -0,0 +1,130 @@
+mod common;
+
+use blue::{
+    BlueComputer, StopReason,
+    device::{Duplex, IoDevice},
+    input::ScriptedInput,
+    output::{OutputSink, Rendering},
+};
+use common::machine;
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Print a zero-terminated string on device 2
+const HELLO: &str = "
+        loop:   LDA ptr
+                ADD load
+                STA fetch
+        fetch:  DW 0
+                JMA done        ; the terminator has the top bit set
+                OUT 2
+                LDA ptr
+                ADD one
+                STA ptr
+                JMP loop
+        done:   HLT
+        ptr:    DW text
+        load:   LDA 0
+        one:    DW 1
+        text:   DW 0x4800, 0x6900, 0x0A00, 0x0700, 0x8000
+";
+
+fn run(source: &str, sink: OutputSink) -> BlueComputer {
+    let mut computer = machine(source);
+    computer.bus_mut().attach(2, sink);
+    assert_eq!(computer.run_until(10_000), StopReason::Halted);
+    computer
+}
+
+#[test]
+fn capture_records_every_byte() {
+    let computer = run(HELLO, OutputSink::capture());
+    let sink = computer.bus().device::<OutputSink>(2).unwrap();
+    assert_eq!(sink.bytes(), b"Hi\n\x07");
+}
+
+#[test]
+fn renderings() {
+    let mut computer = run(HELLO, OutputSink::capture());
+    let sink = computer.bus_mut().device_mut::<OutputSink>(2).unwrap();
+    assert_eq!(sink.rendered(Rendering::Raw), b"Hi\n\x07");
+    assert_eq!(sink.rendered(Rendering::Ascii), b"Hi\n\\x07");
+    assert_eq!(sink.rendered(Rendering::Hex), b"48\n69\n0a\n07\n");
+    assert_eq!(sink.rendered(Rendering::Decimal), b"72\n105\n10\n7\n");
+    assert_eq!(sink.take(), b"Hi\n\x07");
+    assert!(sink.bytes().is_empty());
+
+    for rendering in Rendering::ALL {
+        assert_eq!(rendering.to_string().parse(), Ok(rendering));
+    }
+    assert!("octal".parse::<Rendering>().is_err());
+}
+
+/// A writer whose contents stay readable after the sink is attached
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_sink_with_a_writer_does_not_keep_its_output() {
+    let written = Shared::default();
+    let computer = run(HELLO, OutputSink::new(written.clone(), Rendering::Hex));
+    assert_eq!(written.0.take(), b"48\n69\n0a\n07\n");
+    let sink = computer.bus().device::<OutputSink>(2).unwrap();
+    assert!(sink.bytes().is_empty());
+}
+
+#[test]
+fn duplex_shares_a_selector() {
+    let mut computer = machine("loop: INP 0\n OUT 0\n JMP loop");
+    computer.bus_mut().attach(
+        0,
+        Duplex::new(ScriptedInput::new(*b"ok"), OutputSink::capture()),
+    );
+    computer.run_until(1_000);
+
+    let duplex = computer
+        .bus()
+        .device::<Duplex<ScriptedInput, OutputSink>>(0)
+        .unwrap();
+    assert_eq!(duplex.input.remaining(), 0);
+    assert_eq!(duplex.output.bytes(), b"ok");
+}
+
+/// A printer that never finishes its last character
+struct Jammed;
+
+impl IoDevice for Jammed {
+    fn write_byte(&mut self, _byte: u8) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn ready(&self, _input: bool) -> bool {
+        false
+    }
+}
+
+#[test]
+fn a_busy_printer_does_not_hold_up_the_keyboard() {
+    let mut computer = machine("INP 0\nOUT 0\nHLT");
+    computer
+        .bus_mut()
+        .attach(0, Duplex::new(ScriptedInput::new(*b"k"), Jammed));
+    assert_eq!(computer.run_until(100), StopReason::CycleLimit);
+    assert_eq!(computer.a(), u16::from(b'k') << 8);
+    // The OUT is still waiting for the printer
+    assert_eq!(computer.pc(), 2);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use blue::{
+    BlueComputer, ExecutionMode, FaultKind, IsaProfile, OverflowPolicy, RunState, StopReason,
+};
+use common::{machine_with, symbol};
+
+const FLAG_OVERFLOW: u16 = 0b0100;
+
+/// Overflow with ADD (0x7FFF + 1), then with SUB (0x8000 - 1) if execution goes on
+const SOURCE: &str = "
+        start:  LDA max
+                ADD one
+        after:  LDA min
+                SUB one
+                HLT
+        trap:   STA saved
+                HLT
+        max:    DW 0x7FFF
+        min:    DW 0x8000
+        one:    DW 1
+        saved:  DW 0
+";
+
+fn run(
+    profile: IsaProfile,
+    policy: Option<OverflowPolicy>,
+    mode: ExecutionMode,
+) -> (BlueComputer, StopReason) {
+    let mut computer = machine_with(SOURCE, profile, mode);
+    computer.set_overflow_policy(policy);
+    let stop = computer.run_until(100);
+    (computer, stop)
+}
+
+#[test]
+fn profiles_choose_a_default() {
+    let (computer, stop) = run(IsaProfile::Flags, None, ExecutionMode::CycleAccurate);
+    assert_eq!(stop, StopReason::Overflow);
+    assert_eq!(computer.a(), 0x8000);
+    assert_eq!(computer.flags() & FLAG_OVERFLOW, FLAG_OVERFLOW);
+
+    let (computer, stop) = run(IsaProfile::Extended, None, ExecutionMode::CycleAccurate);
+    assert_eq!(stop, StopReason::Halted);
+    assert_eq!(computer.a(), 0x7FFF);
+    assert_eq!(computer.flags() & FLAG_OVERFLOW, FLAG_OVERFLOW);
+}
+
+#[test]
+fn halt_applies_to_sub() {
+    let mut computer = machine_with(
+        "LDA min\nSUB one\nHLT\nmin: DW 0x8000\none: DW 1",
+        IsaProfile::Extended,
+        ExecutionMode::CycleAccurate,
+    );
+    computer.set_overflow_policy(Some(OverflowPolicy::Halt));
+    assert_eq!(computer.run_until(100), StopReason::Overflow);
+    assert_eq!(computer.a(), 0x7FFF);
+}
+
+#[test]
+fn wrap_continues_without_the_flag() {
+    let policy = Some(OverflowPolicy::Wrap);
+    let (computer, stop) = run(IsaProfile::Extended, policy, ExecutionMode::CycleAccurate);
+    assert_eq!(stop, StopReason::Halted);
+    assert_eq!(computer.flags() & FLAG_OVERFLOW, 0);
+}
+
+#[test]
+fn trap_jumps_to_the_handler() {
+    let policy = Some(OverflowPolicy::Trap(symbol(SOURCE, "trap")));
+    for mode in [ExecutionMode::CycleAccurate, ExecutionMode::Fast] {
+        let (computer, stop) = run(IsaProfile::Flags, policy, mode);
+        assert_eq!(stop, StopReason::Halted);
+        assert_eq!(computer.read_memory(symbol(SOURCE, "saved")), Some(0x8000));
+        assert_eq!(computer.ipc(), symbol(SOURCE, "after"));
+    }
+}
+
+#[test]
+fn fault_stops_with_an_overflow_fault() {
+    let policy = Some(OverflowPolicy::Fault);
+    let [accurate, fast] = [ExecutionMode::CycleAccurate, ExecutionMode::Fast].map(|mode| {
+        let (computer, stop) = run(IsaProfile::Flags, policy, mode);
+        let StopReason::Fault(fault) = stop else {
+            panic!("{mode:?}: {stop:?}");
+        };
+        assert_eq!(computer.run_state(), &RunState::Faulted(fault.clone()));
+        fault
+    });
+    assert_eq!(accurate, fast);
+    assert_eq!(accurate.kind, FaultKind::Overflow);
+    assert_eq!(
+        (accurate.pc, accurate.ir),
+        (symbol(SOURCE, "after"), 0x1009)
+    );
+    assert_eq!(
+        accurate.to_string(),
+        "arithmetic overflow (PC: 0002 IR: 1009)"
+    );
+}
+
+#[test]
+fn fast_mode_reports_overflow_too() {
+    for profile in IsaProfile::ALL {
+        let (accurate, accurate_stop) = run(profile, None, ExecutionMode::CycleAccurate);
+        let (fast, fast_stop) = run(profile, None, ExecutionMode::Fast);
+        assert_eq!(accurate_stop, fast_stop);
+        assert_eq!(accurate.save_state(), fast.save_state());
+    }
+}
+
+#[test]
+fn parse_policies() {
+    for policy in [
+        OverflowPolicy::Halt,
+        OverflowPolicy::Flag,
+        OverflowPolicy::Wrap,
+        OverflowPolicy::Fault,
+        OverflowPolicy::Trap(0x123),
+    ] {
+        assert_eq!(policy.to_string().parse(), Ok(policy));
+    }
+    assert_eq!("trap:0x40".parse(), Ok(OverflowPolicy::Trap(0x40)));
+    assert!("trap:1000".parse::<OverflowPolicy>().is_err());
+    assert!("ignore".parse::<OverflowPolicy>().is_err());
+}
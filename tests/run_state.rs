@@ -0,0 +1,95 @@
+mod common;
+
+use blue::{FaultKind, HaltReason, PauseReason, RunState, StopReason, device::UnattachedPolicy};
+use common::{machine, symbol};
+
+/// Halt twice, then read from a device that is not attached
+const SOURCE: &str = "
+        first:  HLT
+        second: HLT
+                INP 9
+                HLT
+";
+
+#[test]
+fn hlt_stops_until_started_again() {
+    let mut computer = machine(SOURCE);
+    computer.press_off();
+    assert_eq!(computer.run_state(), &RunState::PoweredOff);
+    assert!(!computer.resume());
+    assert_eq!(computer.run_until(10), StopReason::Halted);
+
+    computer.press_on();
+    assert_eq!(computer.run_until(10), StopReason::Halted);
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Halted(HaltReason::Instruction)
+    );
+    assert!(computer.is_powered());
+    assert_eq!(computer.run_until(10), StopReason::Halted);
+    assert_eq!(computer.pc(), symbol(SOURCE, "second"));
+
+    // START carries on from the word after the HLT
+    assert!(computer.resume());
+    assert_eq!(computer.run_until(10), StopReason::Halted);
+    assert_eq!(computer.pc(), symbol(SOURCE, "second") + 1);
+}
+
+#[test]
+fn breakpoints_pause_and_the_next_run_resumes() {
+    let mut computer = machine(SOURCE);
+    computer.add_breakpoint(symbol(SOURCE, "second"));
+    computer.write_memory(symbol(SOURCE, "first"), 0xF000); // NOP
+
+    assert_eq!(
+        computer.run_until(10),
+        StopReason::Breakpoint(symbol(SOURCE, "second"))
+    );
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Paused(PauseReason::Breakpoint(symbol(SOURCE, "second")))
+    );
+    assert_eq!(computer.run_until(10), StopReason::Halted);
+}
+
+#[test]
+fn faults_stick_until_powered_on() {
+    let mut computer = machine(SOURCE);
+    computer
+        .bus_mut()
+        .set_unattached_policy(UnattachedPolicy::Fault);
+    computer.run_until(10);
+    computer.resume();
+    computer.run_until(10);
+    computer.resume();
+
+    let StopReason::Fault(fault) = computer.run_until(10) else {
+        panic!("expected a fault");
+    };
+    assert_eq!(fault.kind, FaultKind::UnattachedDevice(9));
+    assert_eq!(computer.run_state(), &RunState::Faulted(fault.clone()));
+    assert_eq!(computer.run_until(10), StopReason::Fault(fault));
+    assert!(!computer.resume());
+
+    computer.press_on();
+    assert_eq!(computer.run_state(), &RunState::Running);
+}
+
+#[test]
+fn restored_machines_come_back_as_they_were_saved() {
+    let mut computer = machine(SOURCE);
+    let running = computer.save_state();
+    computer.run_until(10);
+    let halted = computer.save_state();
+
+    computer.load_state(&running);
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Paused(PauseReason::Console)
+    );
+    computer.load_state(&halted);
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Halted(HaltReason::Instruction)
+    );
+}
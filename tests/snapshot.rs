@@ -0,0 +1,267 @@
+mod common;
+
+use blue::{
+    BlueComputer, ExecutionMode, Fault, FaultKind, HaltReason, IsaProfile, RunState, StopReason,
+    device::IoDevice,
+    snapshot::{Snapshot, SnapshotError},
+};
+use common::machine_with;
+use std::io;
+
+const SOURCE: &str = "
+        loop:   LDA count
+                ADD one
+                STA count
+                JMP loop
+        count:  DW 0
+        one:    DW 1
+";
+
+/// The counter with a breakpoint, so breakpoints are saved too
+fn machine() -> BlueComputer {
+    let mut computer = machine_with(SOURCE, IsaProfile::Flags, ExecutionMode::CycleAccurate);
+    computer.add_breakpoint(0x100);
+    computer
+}
+
+/// A machine stopped mid-instruction, so the FETCH/EXECUTE state and tick matter
+fn paused() -> BlueComputer {
+    let mut computer = machine();
+    assert_eq!(computer.run_until(25), StopReason::CycleLimit);
+    for _ in 0..3 {
+        computer.step_tick();
+    }
+    computer
+}
+
+#[test]
+fn resumed_machine_continues_identically() {
+    let snapshot = paused().save_state();
+    for restored in [
+        Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(),
+        snapshot.to_string().parse().unwrap(),
+    ] {
+        assert_eq!(restored, snapshot);
+        let mut original = paused();
+        let mut resumed = BlueComputer::new();
+        resumed.load_state(&restored);
+        for _ in 0..40 {
+            assert_eq!(resumed.run_until(7), original.run_until(7));
+            assert_eq!(resumed.save_state(), original.save_state());
+        }
+    }
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let mut bytes = machine().save_state().to_bytes();
+    bytes[4] = 4;
+    assert!(matches!(
+        Snapshot::from_bytes(&bytes),
+        Err(SnapshotError::UnsupportedVersion(4))
+    ));
+
+    let text = machine()
+        .save_state()
+        .to_string()
+        .replacen("blue-snapshot 3", "blue-snapshot 9", 1);
+    assert!(matches!(
+        text.parse::<Snapshot>(),
+        Err(SnapshotError::UnsupportedVersion(9))
+    ));
+}
+
+#[test]
+fn version_1_snapshots_still_load() {
+    let snapshot = machine().save_state();
+    // Version 1 had no IPC, the last of the registers after the 9-byte header, and
+    // no run state after RAM
+    let mut bytes = snapshot.to_bytes();
+    bytes[4] = 1;
+    bytes.drain(9 + 11 * 2..9 + 12 * 2);
+    bytes.truncate(bytes.len() - 2 - "paused console".len());
+    assert_eq!(Snapshot::from_bytes(&bytes).unwrap(), snapshot);
+}
+
+#[test]
+fn truncated_snapshots_are_rejected() {
+    let bytes = machine().save_state().to_bytes();
+    assert!(matches!(
+        Snapshot::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(SnapshotError::Malformed(_))
+    ));
+    assert!("blue-snapshot 1\nbogus 1".parse::<Snapshot>().is_err());
+}
+
+/// Both encodings of `snapshot`, decoded again
+fn reloaded(snapshot: &Snapshot) -> [Snapshot; 2] {
+    [
+        Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(),
+        snapshot.to_string().parse().unwrap(),
+    ]
+}
+
+#[test]
+fn halted_and_faulted_machines_stay_stopped() {
+    for (source, profile, run_state) in [
+        (
+            "HLT",
+            IsaProfile::Foster,
+            RunState::Halted(HaltReason::Instruction),
+        ),
+        (
+            "LDA max\nADD max\nHLT\nmax: DW 0x7FFF",
+            IsaProfile::Flags,
+            RunState::Halted(HaltReason::Overflow),
+        ),
+        (
+            "DW 0xF900",
+            IsaProfile::Extended,
+            RunState::Faulted(Fault {
+                kind: FaultKind::InvalidOpcode(0xF900),
+                pc: 1,
+                ir: 0xF900,
+            }),
+        ),
+    ] {
+        let mut computer = machine_with(source, profile, ExecutionMode::CycleAccurate);
+        computer.run_until(10);
+        assert_eq!(computer.run_state(), &run_state);
+
+        let snapshot = computer.save_state();
+        for restored in reloaded(&snapshot) {
+            assert_eq!(restored, snapshot);
+            let mut resumed = BlueComputer::new();
+            resumed.load_state(&restored);
+            assert_eq!(resumed.run_state(), &run_state);
+            assert_eq!(resumed.run_until(10), computer.run_until(10));
+        }
+    }
+}
+
+/// A printer that fails with a message the text format has to escape
+struct Jammed;
+
+impl IoDevice for Jammed {
+    fn write_byte(&mut self, _byte: u8) -> io::Result<()> {
+        Err(io::Error::other("jammed # \"9\"\nno paper"))
+    }
+}
+
+#[test]
+fn io_fault_messages_survive_both_formats() {
+    let mut computer = machine_with(
+        "OUT 2\nHLT",
+        IsaProfile::Foster,
+        ExecutionMode::CycleAccurate,
+    );
+    computer.bus_mut().attach(2, Jammed);
+    assert!(matches!(computer.run_until(10), StopReason::Fault(_)));
+    let RunState::Faulted(Fault {
+        kind: FaultKind::Io(message),
+        ..
+    }) = computer.run_state()
+    else {
+        panic!("{:?}", computer.run_state());
+    };
+    assert!(message.contains("jammed # \"9\"\nno paper"), "{message}");
+
+    let snapshot = computer.save_state();
+    for restored in reloaded(&snapshot) {
+        assert_eq!(restored, snapshot);
+        let mut resumed = BlueComputer::new();
+        resumed.load_state(&restored);
+        assert_eq!(resumed.run_state(), computer.run_state());
+    }
+}
+
+#[test]
+fn long_io_fault_messages_are_cut_at_a_character_boundary() {
+    struct Rambling;
+
+    impl IoDevice for Rambling {
+        fn write_byte(&mut self, _byte: u8) -> io::Result<()> {
+            Err(io::Error::other(format!("x{}", "é".repeat(40_000))))
+        }
+    }
+
+    let mut computer = machine_with(
+        "OUT 2\nHLT",
+        IsaProfile::Foster,
+        ExecutionMode::CycleAccurate,
+    );
+    computer.bus_mut().attach(2, Rambling);
+    computer.run_until(10);
+    for restored in reloaded(&computer.save_state()) {
+        let mut resumed = BlueComputer::new();
+        resumed.load_state(&restored);
+        let RunState::Faulted(Fault {
+            kind: FaultKind::Io(message),
+            ..
+        }) = resumed.run_state()
+        else {
+            panic!("{:?}", resumed.run_state());
+        };
+        assert!(message.len() <= 1024, "{} bytes", message.len());
+        assert!(message.ends_with('é'));
+    }
+}
+
+#[test]
+fn run_states_survive_both_formats() {
+    let text = machine().save_state().to_string();
+    for run in [
+        "off",
+        "paused step",
+        "paused breakpoint 005",
+        "paused watchpoint 2 003 007 write 0001 0002",
+        "paused tick 07 execute 3",
+        "halted eof",
+        "faulted 0003 c009 eof 09",
+        "faulted 0002 1009 overflow",
+        r#"faulted 0003 c009 io "device 9:  broken pipe""#,
+        r#"faulted 0003 c009 io "jammed # \"9\"\nstill \\ jammed""#,
+    ] {
+        let edited: Snapshot = text
+            .replacen("run paused console", &format!("run {run}"), 1)
+            .parse()
+            .unwrap();
+        assert!(
+            edited.to_string().contains(&format!("\nrun {run}\n")),
+            "{run}"
+        );
+        for restored in reloaded(&edited) {
+            assert_eq!(restored, edited, "{run}");
+        }
+    }
+
+    let faulted = text.replacen(
+        "run paused console",
+        r#"run faulted 0003 c009 io "gone" # since 1975"#,
+        1,
+    );
+    let mut computer = BlueComputer::new();
+    computer.load_state(&faulted.parse().unwrap());
+    assert_eq!(
+        computer.run_state(),
+        &RunState::Faulted(Fault {
+            kind: FaultKind::Io("gone".into()),
+            pc: 3,
+            ir: 0xC009,
+        })
+    );
+
+    for run in [
+        "halted nap",
+        "paused tick 07 execute 8",
+        "faulted 0003 c009",
+        "faulted 0003 c009 io gone",
+        r#"faulted 0003 c009 io "gone"#,
+    ] {
+        let bad = text.replacen("run paused console", &format!("run {run}"), 1);
+        assert!(
+            matches!(bad.parse::<Snapshot>(), Err(SnapshotError::Malformed(_))),
+            "{run}"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use blue::{
+    BlueComputer, FaultKind, StopReason,
+    device::EndOfInputPolicy,
+    tape::{TapePunch, TapeReader},
+};
+use common::machine;
+use std::{fs, path::PathBuf};
+
+/// Copy the tape in the reader to the punch
+const COPY: &str = "
+        loop:   INP 1
+                OUT 2
+                JMP loop
+";
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("blue-{}-{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn copier(reader: TapeReader, punch: TapePunch, policy: EndOfInputPolicy) -> BlueComputer {
+    let mut computer = machine(COPY);
+    computer.bus_mut().attach(1, reader);
+    computer.bus_mut().attach(2, punch);
+    computer.bus_mut().set_end_of_input_policy(policy);
+    computer
+}
+
+#[test]
+fn copies_a_tape_file() {
+    let source = temp_path("source.tape");
+    let copy = temp_path("copy.tape");
+    let data: Vec<u8> = (0..=255).collect();
+    fs::write(&source, &data).unwrap();
+
+    let reader = TapeReader::open(&source).unwrap();
+    let punch = TapePunch::create(&copy).unwrap();
+    let mut computer = copier(reader, punch, EndOfInputPolicy::Halt);
+    assert_eq!(computer.run_until(100_000), StopReason::Halted);
+
+    let reader = computer.bus().device::<TapeReader>(1).unwrap();
+    assert_eq!(reader.position(), data.len());
+    assert_eq!(
+        computer.bus().device::<TapePunch>(2).unwrap().punched(),
+        256
+    );
+    assert_eq!(fs::read(&copy).unwrap(), data);
+
+    fs::remove_file(source).unwrap();
+    fs::remove_file(copy).unwrap();
+}
+
+#[test]
+fn punch_appends_to_an_existing_tape() {
+    let path = temp_path("append.tape");
+    fs::write(&path, b"AB").unwrap();
+
+    let punch = TapePunch::append(&path).unwrap();
+    let mut computer = copier(TapeReader::new(*b"CD"), punch, EndOfInputPolicy::Wait);
+    assert_eq!(computer.run_until(10_000), StopReason::WaitingForIo);
+    assert_eq!(fs::read(&path).unwrap(), b"ABCD");
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reading_past_the_end_of_tape_can_fault() {
+    let path = temp_path("fault.tape");
+    let punch = TapePunch::create(&path).unwrap();
+    let mut computer = copier(TapeReader::new(*b"x"), punch, EndOfInputPolicy::Fault);
+    match computer.run_until(10_000) {
+        StopReason::Fault(fault) => assert_eq!(fault.kind, FaultKind::EndOfInput(1)),
+        stop => panic!("expected an end-of-input fault, got {stop:?}"),
+    }
+
+    let reader = computer.bus_mut().device_mut::<TapeReader>(1).unwrap();
+    reader.rewind();
+    assert_eq!(reader.position(), 0);
+    assert_eq!(reader.len(), 1);
+
+    fs::remove_file(path).unwrap();
+}
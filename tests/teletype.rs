@@ -0,0 +1,82 @@
+mod common;
+
+use blue::{
+    StopReason,
+    device::{IoDevice, Latency},
+    teletype::{LineEnding, Teletype},
+};
+use common::machine;
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Paper the teletype prints on, readable after the device is attached
+#[derive(Clone, Default)]
+struct Paper(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Paper {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn echo(keys: &'static [u8], setup: impl FnOnce(&mut Teletype)) -> Vec<u8> {
+    let paper = Paper::default();
+    let mut teletype = Teletype::new(keys, paper.clone());
+    setup(&mut teletype);
+
+    let mut computer = machine(&std::fs::read_to_string("progs/echo.asm").unwrap());
+    computer.bus_mut().attach(3, teletype);
+    assert_eq!(computer.run_until(100_000), StopReason::Halted);
+    paper.0.take()
+}
+
+#[test]
+fn echoes_keys_with_carriage_return_and_line_feed() {
+    assert_eq!(echo(b"Hi\rthere\x1b", |_| ()), b"Hi\r\nthere");
+}
+
+#[test]
+fn newline_mode_reads_return_as_line_feed() {
+    let printed = echo(b"a\rb\x1b", |teletype| {
+        teletype.set_line_ending(LineEnding::Newline);
+    });
+    assert_eq!(printed, b"a\r\nb");
+}
+
+#[test]
+fn upper_case_mode_folds_and_strips_the_eighth_bit() {
+    let printed = echo(b"shout\xa1\x1b", |teletype| teletype.set_upper_case(true));
+    assert_eq!(printed, b"SHOUT!");
+}
+
+#[test]
+fn pacing_holds_each_character_for_a_tenth_of_a_second() {
+    let mut teletype = Teletype::new(io::empty(), io::sink());
+    assert_eq!(teletype.latency(false), Latency::Immediate);
+    teletype.set_paced(true);
+    assert_eq!(teletype.latency(false), Latency::Micros(100_000));
+    assert_eq!(teletype.latency(true), Latency::Immediate);
+}
+
+#[test]
+fn paced_output_waits_in_emulated_time() {
+    let run = |paced: bool, cycles: u64| {
+        let mut teletype = Teletype::new(io::empty(), io::sink());
+        teletype.set_paced(paced);
+        let mut computer = machine("OUT 3\nOUT 3\nHLT");
+        computer.bus_mut().attach(3, teletype);
+        computer.run_until(cycles)
+    };
+    // Two characters at 100,000 ticks each, 8 ticks per cycle
+    assert_eq!(run(false, 100), StopReason::Halted);
+    assert_eq!(run(true, 24_000), StopReason::CycleLimit);
+    assert_eq!(run(true, 26_000), StopReason::Halted);
+}
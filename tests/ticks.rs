@@ -0,0 +1,134 @@
+mod common;
+
+use blue::{
+    ExecutionMode, HaltReason, Instruction, IsaProfile, MicroTick, PauseReason, RunState, State,
+    StopReason,
+    debugger::{Command, Debugger},
+};
+use common::machine_with;
+
+/// Copy the 7 at address 3 to address 4
+const PROGRAM: &str = "
+        LDA 3
+        STA 4
+        HLT
+        DW 7
+        DW 0
+";
+
+fn at(instruction: Instruction, state: State, tick: u8) -> MicroTick {
+    MicroTick {
+        opcode: instruction.opcode(),
+        state,
+        tick,
+    }
+}
+
+#[test]
+fn ticks_follow_the_instruction_being_fetched() {
+    let mut computer = machine_with(PROGRAM, IsaProfile::Foster, ExecutionMode::CycleAccurate);
+    let mut ticks = Vec::new();
+    while computer.step_tick().is_none() {
+        ticks.push(computer.next_tick().unwrap());
+    }
+    // Each tick but the last (the HLT's final one) is followed by the next
+    assert_eq!(ticks.len(), 8 * 5 - 1);
+    assert_eq!(ticks[6], at(Instruction::Lda, State::Fetch, 7));
+    assert_eq!(ticks[7], at(Instruction::Lda, State::Execute, 0));
+    assert_eq!(ticks[15], at(Instruction::Sta, State::Fetch, 0));
+    assert_eq!(ticks[31], at(Instruction::Hlt, State::Fetch, 0));
+    assert_eq!(
+        at(Instruction::Sta, State::Execute, 3).to_string(),
+        "STA execute tick 3"
+    );
+}
+
+#[test]
+fn tick_breakpoints_stop_before_their_tick() {
+    for mode in [ExecutionMode::CycleAccurate, ExecutionMode::Fast] {
+        let mut computer = machine_with(PROGRAM, IsaProfile::Foster, mode);
+        let tick = at(Instruction::Sta, State::Execute, 3);
+        let id = computer.breakpoints_mut().add_tick_breakpoint(tick);
+        assert_eq!(computer.run_until(100), StopReason::TickBreakpoint(tick));
+        assert_eq!(
+            computer.run_state(),
+            &RunState::Paused(PauseReason::TickBreakpoint(tick))
+        );
+        assert_eq!(
+            (computer.state(), computer.clock_pulse()),
+            (State::Execute, 3)
+        );
+        assert_eq!(computer.read_memory(4), Some(0));
+
+        assert_eq!(computer.run_until(100), StopReason::Halted);
+        assert_eq!(computer.read_memory(4), Some(7));
+        assert_eq!(computer.breakpoints().tick_breakpoint(id).unwrap().hits, 1);
+    }
+
+    let mut computer = machine_with(PROGRAM, IsaProfile::Foster, ExecutionMode::CycleAccurate);
+    let id = computer
+        .breakpoints_mut()
+        .add_tick_breakpoint(at(Instruction::Hlt, State::Fetch, 6));
+    computer
+        .breakpoints_mut()
+        .tick_breakpoint_mut(id)
+        .unwrap()
+        .enabled = false;
+    assert_eq!(computer.run_until(100), StopReason::Halted);
+}
+
+#[test]
+fn the_console_stops_at_tick_breakpoints() {
+    let tick = at(Instruction::Sta, State::Execute, 3);
+    for mode in [ExecutionMode::CycleAccurate, ExecutionMode::Fast] {
+        let mut computer = machine_with(PROGRAM, IsaProfile::Foster, mode);
+        computer.set_register_trace(false);
+        computer.pause(PauseReason::Console);
+
+        let mut debugger = Debugger::new();
+        debugger.queue_commands("mbreak STA execute 3\ncontinue\nquit\n");
+        computer.run_console(&mut debugger);
+        assert_eq!(
+            computer.run_state(),
+            &RunState::Paused(PauseReason::TickBreakpoint(tick))
+        );
+        assert_eq!(
+            (computer.state(), computer.clock_pulse()),
+            (State::Execute, 3)
+        );
+        assert_eq!(computer.read_memory(4), Some(0));
+
+        // Continuing runs the tick it stopped before rather than stopping again
+        debugger.queue_commands("continue\nquit\n");
+        computer.run_console(&mut debugger);
+        assert_eq!(
+            computer.run_state(),
+            &RunState::Halted(HaltReason::Instruction)
+        );
+        assert_eq!(computer.read_memory(4), Some(7));
+        let hits = computer.breakpoints().tick_breakpoints().map(|b| b.hits);
+        assert_eq!(hits.collect::<Vec<_>>(), [1]);
+    }
+}
+
+#[test]
+fn console_commands_for_ticks() {
+    assert_eq!("tick".parse(), Ok(Command::Tick(1)));
+    assert_eq!("t 0x10".parse(), Ok(Command::Tick(16)));
+    assert_eq!(
+        "mbreak lda EXECUTE 3".parse(),
+        Ok(Command::TickBreak(at(Instruction::Lda, State::Execute, 3)))
+    );
+    assert_eq!(
+        "mb 17 fetch 0".parse(),
+        Ok(Command::TickBreak(at(Instruction::Cmp, State::Fetch, 0)))
+    );
+    for bad in [
+        "mb * fetch 1",
+        "mb LDA fetch 8",
+        "mb LDA decode 1",
+        "mb FOO fetch 1",
+    ] {
+        assert!(bad.parse::<Command>().is_err(), "{bad}");
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use blue::{
+    ExecutionMode, IsaProfile, PauseReason, RunState, StopReason,
+    breakpoint::{WatchHit, WatchKind},
+    debugger::Command,
+};
+use common::{machine_with, symbol};
+
+/// Fill `table` through a pointer that runs one word past its end, into `code`
+const STRAY: &str = "
+        loop:   LDA fill
+        store:  STA table
+                LDA store
+                ADD one
+                STA store
+                JMP loop
+        table:  DW 0
+                DW 0
+        code:   HLT
+        fill:   DW 0x1234
+        one:    DW 1
+";
+
+#[test]
+fn writes_are_caught_after_the_instruction() {
+    for mode in [ExecutionMode::CycleAccurate, ExecutionMode::Fast] {
+        let mut computer = machine_with(STRAY, IsaProfile::Foster, mode);
+        let id = computer.breakpoints_mut().add_watchpoint(
+            symbol(STRAY, "code"),
+            symbol(STRAY, "code"),
+            WatchKind::Write,
+        );
+        let hit = WatchHit {
+            id,
+            instruction: symbol(STRAY, "store"),
+            address: symbol(STRAY, "code"),
+            write: true,
+            old: 0x0000,
+            new: 0x1234,
+        };
+        assert_eq!(computer.run_until(1_000), StopReason::Watchpoint(hit));
+        assert_eq!(
+            computer.run_state(),
+            &RunState::Paused(PauseReason::Watchpoint(hit))
+        );
+        assert_eq!(computer.pc(), symbol(STRAY, "store") + 1);
+        assert_eq!(computer.breakpoints().watchpoint(id).unwrap().hits, 1);
+    }
+}
+
+#[test]
+fn reads_include_instruction_fetches() {
+    let mut computer = machine_with(STRAY, IsaProfile::Foster, ExecutionMode::CycleAccurate);
+    let table = symbol(STRAY, "table");
+    computer
+        .breakpoints_mut()
+        .add_watchpoint(table + 1, table, WatchKind::Access);
+    let id = computer.breakpoints_mut().add_watchpoint(
+        symbol(STRAY, "one"),
+        symbol(STRAY, "one"),
+        WatchKind::Read,
+    );
+
+    // Both table words are written, then the ADD reads `one`
+    let StopReason::Watchpoint(first) = computer.run_until(1_000) else {
+        panic!("expected a watchpoint");
+    };
+    assert_eq!((first.address, first.write), (table, true));
+    let StopReason::Watchpoint(second) = computer.run_until(1_000) else {
+        panic!("expected a watchpoint");
+    };
+    assert_eq!((second.id, second.write), (id, false));
+    assert_eq!(second.instruction, symbol(STRAY, "store") + 2);
+
+    computer
+        .breakpoints_mut()
+        .watchpoint_mut(id)
+        .unwrap()
+        .enabled = false;
+    let StopReason::Watchpoint(third) = computer.run_until(1_000) else {
+        panic!("expected a watchpoint");
+    };
+    assert_eq!(third.address, table + 1);
+}
+
+#[test]
+fn fast_mode_stops_at_the_same_points() {
+    let mut accurate = machine_with(STRAY, IsaProfile::Foster, ExecutionMode::CycleAccurate);
+    let mut fast = machine_with(STRAY, IsaProfile::Foster, ExecutionMode::Fast);
+    for computer in [&mut accurate, &mut fast] {
+        computer
+            .breakpoints_mut()
+            .add_watchpoint(0, 0x00F, WatchKind::Access);
+    }
+    for _ in 0..30 {
+        assert_eq!(accurate.run_until(1_000), fast.run_until(1_000));
+        assert_eq!(accurate.save_state(), fast.save_state());
+    }
+}
+
+#[test]
+fn console_commands_for_watchpoints() {
+    assert_eq!(
+        "watch 7 8".parse(),
+        Ok(Command::Watch {
+            start: 7,
+            end: 8,
+            kind: WatchKind::Write
+        })
+    );
+    assert_eq!(
+        "rw 0x6".parse(),
+        Ok(Command::Watch {
+            start: 6,
+            end: 6,
+            kind: WatchKind::Read
+        })
+    );
+    for bad in ["watch", "watch 1 2 3", "awatch 4096"] {
+        assert!(bad.parse::<Command>().is_err(), "{bad}");
+    }
+}